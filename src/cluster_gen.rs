@@ -8,15 +8,37 @@ use crate::{
   },
   distance_util::{
     compute_inner_product,
-    compute_vector_length
+    compute_vector_length,
+    compute_vector,
+    compute_geographic_centroid,
+    project_to_local_plane,
+    unproject_from_local_plane
+  },
+  distance_config::{
+    DistanceConfig,
+    CoordinateMode
   }
 };
 use std::collections::HashSet;
 
-static MIN_LINE_SEGMENT_LENGTH: f64 = 50.0;
+/// 默认的平滑参数 γ，即代表轨迹相邻两点在平均方向上的最小间隔，
+/// 取默认的最短线段长度 50 除以 √2
+pub static DEFAULT_GAMMA: f64 = 50.0 / 1.414;
 
+/// 根据 DBSCAN 的结果构建线段簇并计算代表轨迹
+///
+/// 经纬度坐标下，每个簇的线段会先投影到以簇中心为原点的局部切平面上，
+/// 代表轨迹在切平面上计算后再还原为经纬度。
+/// 扫描时同时覆盖的线段始终少于 MinLns、代表点少于两个的簇不会被启用，`construct_cluster` 会舍弃它们
 pub fn construct_line_segment_cluster(max_index: usize, min_lns: usize,
-  cluster_indexs: Vec<i32>, line_segments: Vec<LineSegment>)  -> Vec<LineSegmentCluster>
+  cluster_indexs: &[i32], line_segments: &[LineSegment], config: &DistanceConfig)  -> Vec<LineSegmentCluster>
+{
+  construct_line_segment_cluster_with_gamma(max_index, min_lns, DEFAULT_GAMMA, cluster_indexs, line_segments, config)
+}
+
+/// 使用指定的平滑参数 γ 构建线段簇并计算代表轨迹，γ 越大代表轨迹越平滑、点越少
+pub fn construct_line_segment_cluster_with_gamma(max_index: usize, min_lns: usize, gamma: f64,
+  cluster_indexs: &[i32], line_segments: &[LineSegment], config: &DistanceConfig)  -> Vec<LineSegmentCluster>
 {
   let mut line_segment_clusters: Vec<LineSegmentCluster> = Vec::with_capacity(max_index);
 
@@ -25,44 +47,54 @@ pub fn construct_line_segment_cluster(max_index: usize, min_lns: usize,
     line_segment_clusters.push(LineSegmentCluster::new(i));
   }
 
+  let origins = compute_cluster_origins(max_index, cluster_indexs, line_segments, config);
+  let local_segments = localize_line_segments(cluster_indexs, line_segments, &origins);
+
   // 开始计算各个簇的平均方向向量
   for i in 0..line_segments.len() {
     let index = *cluster_indexs.get(i).unwrap();
     if index >= 0 {
-      let (start_point, end_point) = line_segments.get(i).unwrap().extract_start_end_points();
+      let (start_point, end_point) = local_segments.get(i).unwrap();
 
-      let diff_x: f64 = end_point.get_x() - start_point.get_x();
-      let diff_y: f64 = end_point.get_y() - start_point.get_y();
-      line_segment_clusters.get_mut(index as usize).unwrap().add_x(diff_x);
-      line_segment_clusters.get_mut(index as usize).unwrap().add_y(diff_y);
+      let direction_vector = compute_vector(start_point, end_point);
+      line_segment_clusters.get_mut(index as usize).unwrap().add_direction_vector(&direction_vector);
 
       line_segment_clusters.get_mut(index as usize).unwrap().add_num_of_line_segments();
+
+      // 记录簇的时间范围
+      let line_segment = line_segments.get(i).unwrap();
+      for t in line_segment.get_start_time().into_iter().chain(line_segment.get_end_time()) {
+        line_segment_clusters.get_mut(index as usize).unwrap().extend_time_range(t);
+      }
     }
   }
 
-  let vector = Point::new(1.0, 0.0);
+  // 计算平均方向上的单位向量，扫描线沿着该方向移动
   for i in 0..max_index {
     let cluster_entry = line_segment_clusters.get_mut(i).unwrap();
 
-    cluster_entry.avg_direction_vector_x();
-    cluster_entry.avg_direction_vector_y();
-    
+    cluster_entry.avg_direction_vector();
+
     let avg_direction_vector = cluster_entry.get_avg_direcation_vector();
     let vector_length = compute_vector_length(avg_direction_vector);
-    let inner_product = compute_inner_product(avg_direction_vector, &vector);
-    let mut cos_theta = inner_product / vector_length;
-    cos_theta = if cos_theta > 1.0 { 1.0 } else if cos_theta < -1.0 { -1.0 } else { cos_theta };
-    let mut sin_theta = (1.0 - cos_theta.powi(2)).sqrt();
-    sin_theta = if avg_direction_vector.get_y() < 0.0 { -sin_theta } else { sin_theta };
+    let mut unit_direction_vector = Point::zero(avg_direction_vector.get_dimension());
+    if vector_length > 0.0 {
+      for j in 0..unit_direction_vector.get_dimension() {
+        unit_direction_vector.set_coord(j, avg_direction_vector.get_coord(j) / vector_length);
+      }
+    } else {
+      unit_direction_vector.set_x(1.0);
+    }
 
-    cluster_entry.set_theta(cos_theta, sin_theta);
+    cluster_entry.set_unit_direction_vector(unit_direction_vector);
   }
 
   // 构建在平均方向上的候选点
   for i in 0..line_segments.len() {
     let index = *cluster_indexs.get(i).unwrap();
     if index >= 0 {
-      get_candidate_points(line_segment_clusters.get_mut(index as usize).unwrap(), line_segments.get(i).unwrap(), i);
+      get_candidate_points(line_segment_clusters.get_mut(index as usize).unwrap(),
+        local_segments.get(i).unwrap(), line_segments.get(i).unwrap().get_trajectory_id(), i);
     }
   }
 
@@ -71,24 +103,79 @@ pub fn construct_line_segment_cluster(max_index: usize, min_lns: usize,
     line_segment_cluster.sort();
   }
 
-  // 根据候选点计算扫描线
-  for line_segment_cluster in line_segment_clusters.iter_mut() {
-    if line_segment_cluster.get_trajectory_length() >= min_lns {
+  // 参与轨迹数不足的簇已经在 DBSCAN 中被标记为噪声，这里的簇都是有效的
+
+  // 根据候选点计算扫描线，代表点少于两个的簇无法构成代表轨迹，不会被启用
+  for (i, line_segment_cluster) in line_segment_clusters.iter_mut().enumerate() {
+    if line_segment_cluster.get_trajectory_length() > 0
+      && compute_representative_lines(min_lns, gamma, line_segment_cluster, line_segments, &local_segments,
+        origins[i].as_ref()) > 0
+    {
       line_segment_cluster.enable();
-      compute_representative_lines(min_lns, line_segment_cluster, &line_segments);
     }
   }
 
   line_segment_clusters
 }
 
+/// 计算经纬度坐标下每个簇的局部切平面原点，平面坐标下总是 None
+fn compute_cluster_origins(max_index: usize, cluster_indexs: &[i32], line_segments: &[LineSegment],
+  config: &DistanceConfig) -> Vec<Option<Point>>
+{
+  if config.get_coordinate_mode() == CoordinateMode::Planar {
+    return vec![None; max_index];
+  }
+
+  let mut cluster_points: Vec<Vec<&Point>> = vec![Vec::new(); max_index];
+  for (i, line_segment) in line_segments.iter().enumerate() {
+    let index = *cluster_indexs.get(i).unwrap();
+    if index >= 0 {
+      let (start_point, end_point) = line_segment.extract_start_end_points();
+      cluster_points[index as usize].push(start_point);
+      cluster_points[index as usize].push(end_point);
+    }
+  }
+
+  cluster_points.into_iter()
+    .map(|points| if points.is_empty() { None } else { Some(compute_geographic_centroid(&points)) })
+    .collect()
+}
+
+/// 获得用于计算代表轨迹的线段端点，有原点的簇中的线段会投影到局部切平面上
+fn localize_line_segments(cluster_indexs: &[i32], line_segments: &[LineSegment],
+  origins: &[Option<Point>]) -> Vec<(Point, Point)>
+{
+  line_segments.iter()
+    .enumerate()
+    .map(|(i, line_segment)| {
+      let (start_point, end_point) = line_segment.extract_start_end_points();
+      let index = *cluster_indexs.get(i).unwrap();
+
+      match origins.get(index as usize).and_then(Option::as_ref) {
+        Some(origin) if index >= 0 => {
+          (project_to_local_plane(start_point, origin), project_to_local_plane(end_point, origin))
+        },
+        _ => (*start_point, *end_point)
+      }
+    })
+    .collect()
+}
+
 pub fn construct_cluster(line_segment_clusters: Vec<LineSegmentCluster>) -> Vec<Cluster> {
   let mut curr_id: usize = 0;
   let clusters: Vec<Cluster> = line_segment_clusters.into_iter()
     .filter_map(|line_segment_cluster| {
       if !line_segment_cluster.get_enable() { return None; }
-      
-      let cluster = Cluster::new(curr_id, line_segment_cluster.get_points());
+
+      let time_range = line_segment_cluster.get_time_range();
+      let mut cluster = Cluster::new(
+        curr_id,
+        line_segment_cluster.get_id(),
+        line_segment_cluster.get_num_of_line_segments(),
+        line_segment_cluster.get_trajectory_ids(),
+        line_segment_cluster.get_points()
+      );
+      cluster.set_time_range(time_range);
       curr_id += 1;
 
       Some(cluster)
@@ -98,47 +185,33 @@ pub fn construct_cluster(line_segment_clusters: Vec<LineSegmentCluster>) -> Vec<
   clusters
 }
 
-fn get_candidate_points(cluster: &mut LineSegmentCluster, line_segment: &LineSegment, line_index: usize) {
-  let (start_point, end_point) = line_segment.extract_start_end_points();
-  // ordering_value 是点 X 坐标在平均方向上的投影
-  let ordering_value_1 = get_x_rotation(
-    start_point.get_x(), 
-    start_point.get_y(),
-    cluster.get_cos(), cluster.get_sin());
-  let ordering_value_2 = get_x_rotation(
-    end_point.get_x(), 
-    end_point.get_y(),
-    cluster.get_cos(), cluster.get_sin());
+/// 保留时间范围与指定时间段重叠的簇，簇的 id 保持不变
+pub fn filter_clusters_by_time(clusters: Vec<Cluster>, start: f64, end: f64) -> Vec<Cluster> {
+  clusters.into_iter()
+    .filter(|cluster| cluster.overlaps_time_window(start, end))
+    .collect()
+}
+
+fn get_candidate_points(cluster: &mut LineSegmentCluster, local_segment: &(Point, Point),
+  trajectory_id: usize, line_index: usize)
+{
+  let (start_point, end_point) = local_segment;
+  // ordering_value 是点在平均方向上的投影
+  let ordering_value_1 = compute_inner_product(start_point, cluster.get_unit_direction_vector());
+  let ordering_value_2 = compute_inner_product(end_point, cluster.get_unit_direction_vector());
 
   let candidate_point_1 = CandidatePoint::new(line_index, ordering_value_1);
   let candidate_point_2 = CandidatePoint::new(line_index, ordering_value_2);
   cluster.push(candidate_point_1);
   cluster.push(candidate_point_2);
 
-  let trajectory_id = line_segment.get_trajectory_id();
   if !cluster.contains(&trajectory_id) {
     cluster.insert(trajectory_id);
   }
 }
 
-fn get_x_rotation(x: f64, y: f64, cos: f64, sin: f64) -> f64 {
-  x * cos + y * sin
-}
-
-fn get_y_rotation(x: f64, y: f64, cos: f64, sin: f64) -> f64 {
-  -x * sin + y * cos
-}
-
-fn get_x_rev_rotation(x: f64, y: f64, cos: f64, sin: f64) -> f64 {
-  x * cos - y * sin
-}
-
-fn get_y_rev_rotation(x: f64, y: f64, cos: f64, sin: f64) -> f64 {
-  x * sin + y * cos
-}
-
-fn compute_representative_lines(min_lns: usize,
-  cluster: &mut LineSegmentCluster, line_segments: &Vec<LineSegment>) -> usize {
+fn compute_representative_lines(min_lns: usize, gamma: f64, cluster: &mut LineSegmentCluster,
+  line_segments: &[LineSegment], local_segments: &[(Point, Point)], origin: Option<&Point>) -> usize {
   let mut line_segments_list: HashSet<usize> = HashSet::new();
   let mut insertion_list: HashSet<usize> = HashSet::new();
   let mut deletion_list: HashSet<usize> = HashSet::new();
@@ -146,12 +219,14 @@ fn compute_representative_lines(min_lns: usize,
   let mut iter = 0;
   let mut candidate_point: &CandidatePoint;
   let mut next_candidate_point: &CandidatePoint;
-  let mut prev_ordering_value: f64 = 0.0;
+  // 第一个满足条件的扫描位置总是会生成代表点，
+  // 否则投影在 0 附近的簇（例如反方向的簇）会丢失起点
+  let mut prev_ordering_value: Option<f64> = None;
   let mut cluster_points: usize = 0;
 
   line_segments_list.clear();
 
-  while iter != (cluster.len() - 1) && cluster.len() > 0 {
+  while iter != (cluster.len() - 1) && !cluster.is_empty() {
     insertion_list.clear();
     deletion_list.clear();
 
@@ -210,16 +285,22 @@ fn compute_representative_lines(min_lns: usize,
     }
 
     let mut point: Option<Point> = None;
-    if line_segments_list.len() >= min_lns {
-      if (candidate_point.get_ordering_value() - prev_ordering_value).abs() > (MIN_LINE_SEGMENT_LENGTH / 1.414) {
-        point = Some(compute_cluster_point(cluster, line_segments, candidate_point.get_ordering_value(), &line_segments_list));
-        prev_ordering_value = candidate_point.get_ordering_value();
-        cluster_points += 1;
-      }
+    if line_segments_list.len() >= min_lns
+      && prev_ordering_value.is_none_or(|prev| {
+        (candidate_point.get_ordering_value() - prev).abs() > gamma
+      })
+    {
+      point = Some(compute_cluster_point(cluster, local_segments, candidate_point.get_ordering_value(), &line_segments_list));
+      prev_ordering_value = Some(candidate_point.get_ordering_value());
+      cluster_points += 1;
     }
 
     if let Some(point) = point {
-      cluster.add_point(point);
+      // 将代表点还原到原坐标系中
+      match origin {
+        Some(origin) => cluster.add_point(unproject_from_local_plane(&point, origin)),
+        None => cluster.add_point(point)
+      }
     }
 
     for deletion in deletion_list.iter() {
@@ -231,58 +312,42 @@ fn compute_representative_lines(min_lns: usize,
     return cluster_points;
   }
 
-  return 0;
+  0
 }
 
-fn compute_cluster_point(cluster: &LineSegmentCluster, line_segments: &Vec<LineSegment>,
+fn compute_cluster_point(cluster: &LineSegmentCluster, local_segments: &[(Point, Point)],
   value: f64, line_segments_list: &HashSet<usize>) -> Point
 {
   let line_segments_len = line_segments_list.len();
-  let mut cluster_point = Point::init();
+  let mut cluster_point = Point::zero(cluster.get_unit_direction_vector().get_dimension());
   let mut sweep_point: Point;
 
+  // 扫描点已经在原坐标系中，直接求平均即可
   for line_segment_id in line_segments_list {
-    sweep_point = get_sweep_point(cluster, value, line_segments.get(*line_segment_id).unwrap());
-    let coordinate_x = cluster_point.get_x() + sweep_point.get_x() / line_segments_len as f64;
-    let coordinate_y = cluster_point.get_y() + sweep_point.get_y() / line_segments_len as f64;
-
-    cluster_point.set_x(coordinate_x);
-    cluster_point.set_y(coordinate_y);
+    sweep_point = get_sweep_point(cluster, value, &local_segments[*line_segment_id]);
+    for i in 0..cluster_point.get_dimension() {
+      let coordinate = cluster_point.get_coord(i) + sweep_point.get_coord(i) / line_segments_len as f64;
+      cluster_point.set_coord(i, coordinate);
+    }
   }
 
-  let orig_x = get_x_rev_rotation(
-    cluster_point.get_x(), 
-    cluster_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-  let orig_y = get_y_rev_rotation(
-    cluster_point.get_x(), 
-    cluster_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-
-  cluster_point.set_x(orig_x);
-  cluster_point.set_y(orig_y);
-
   cluster_point
 }
 
-fn get_sweep_point(cluster: &LineSegmentCluster, value: f64, line_segment: &LineSegment) -> Point {
-  let (start_point, end_point) = line_segment.extract_start_end_points();
-
-  let new_start_x = get_x_rotation(start_point.get_x(),
-    start_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-  let new_end_x = get_x_rotation(end_point.get_x(),
-    end_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-  let new_start_y = get_y_rotation(start_point.get_x(),
-    start_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-  let new_end_y = get_y_rotation(end_point.get_x(),
-    end_point.get_y(), 
-    cluster.get_cos(), cluster.get_sin());
-
-  let cofficient = (value - new_start_x) / (new_end_x - new_start_x);
-  let sweep_point = Point::new(value, new_start_y + cofficient * (new_end_y - new_start_y));
+/// 获得线段上在平均方向上的投影为 value 的点
+fn get_sweep_point(cluster: &LineSegmentCluster, value: f64, local_segment: &(Point, Point)) -> Point {
+  let (start_point, end_point) = local_segment;
+  let unit_direction_vector = cluster.get_unit_direction_vector();
+
+  let new_start = compute_inner_product(start_point, unit_direction_vector);
+  let new_end = compute_inner_product(end_point, unit_direction_vector);
+
+  let cofficient = (value - new_start) / (new_end - new_start);
+  let vector = compute_vector(start_point, end_point);
+  let mut sweep_point = Point::zero(vector.get_dimension());
+  for i in 0..sweep_point.get_dimension() {
+    sweep_point.set_coord(i, start_point.get_coord(i) + cofficient * vector.get_coord(i));
+  }
 
   sweep_point
-}
\ No newline at end of file
+}
@@ -0,0 +1,332 @@
+//! TraClus 的完整流程：划分轨迹、对线段执行 DBSCAN、生成代表轨迹
+//!
+//! 库的使用者只需要配置 `TraClus` 并调用一次 `run`，
+//! 不需要自己按顺序调用各个阶段并在它们之间传递簇的数量与线段标签。
+use crate::{
+  models::{
+    trajectory::Trajectory,
+    thick_trajectory::ThickTrajectory,
+    line_segment::LineSegment,
+    cluster::Cluster
+  },
+  partition_tra::{
+    partition_trajectories,
+    get_partition_line
+  },
+  dbscan::{
+    perform_dbscan,
+    perform_dbscan_with_threads
+  },
+  cluster_gen::{
+    construct_line_segment_cluster_with_gamma,
+    construct_cluster,
+    DEFAULT_GAMMA
+  },
+  distance_config::DistanceConfig,
+  partition_config::PartitionConfig
+};
+use rayon::ThreadPoolBuildError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+/// 运行 TraClus 时的错误
+#[derive(Debug)]
+pub enum TraClusError {
+  // 参数无效，包含参数名
+  InvalidParameter(&'static str),
+  // 无法创建指定线程数的线程池
+  ThreadPool(ThreadPoolBuildError),
+}
+
+impl fmt::Display for TraClusError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TraClusError::InvalidParameter(name) => write!(f, "the parameter `{}` isn't valid", name),
+      TraClusError::ThreadPool(e) => write!(f, "can't create the thread pool: {}", e)
+    }
+  }
+}
+
+impl Error for TraClusError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      TraClusError::ThreadPool(e) => Some(e),
+      _ => None
+    }
+  }
+}
+
+/// TraClus 的参数
+#[derive(Debug, Clone)]
+pub struct TraClus {
+  eps: f64,
+  min_lns: usize,
+  min_trajectories: Option<usize>,
+  distance_config: DistanceConfig,
+  gamma: f64,
+  partition_config: PartitionConfig,
+  num_threads: Option<usize>,
+}
+
+impl TraClus {
+  /// 创建一组参数，其余参数使用默认值：
+  /// 默认的距离配置、参与轨迹数阈值与 MinLns 相同、默认的 γ 与划分配置、使用全局线程池
+  pub fn new(eps: f64, min_lns: usize) -> Self {
+    Self {
+      eps,
+      min_lns,
+      min_trajectories: None,
+      distance_config: DistanceConfig::default(),
+      gamma: DEFAULT_GAMMA,
+      partition_config: PartitionConfig::default(),
+      num_threads: None,
+    }
+  }
+
+  /// 设置 eps
+  pub fn set_eps(&mut self, eps: f64) -> &mut Self {
+    self.eps = eps;
+    self
+  }
+
+  /// 设置 MinLns
+  pub fn set_min_lns(&mut self, min_lns: usize) -> &mut Self {
+    self.min_lns = min_lns;
+    self
+  }
+
+  /// 设置参与轨迹数的阈值，None 表示与 MinLns 相同
+  pub fn set_min_trajectories(&mut self, min_trajectories: Option<usize>) -> &mut Self {
+    self.min_trajectories = min_trajectories;
+    self
+  }
+
+  /// 设置线段距离的配置
+  pub fn set_distance_config(&mut self, distance_config: DistanceConfig) -> &mut Self {
+    self.distance_config = distance_config;
+    self
+  }
+
+  /// 设置线段距离的权重 w⊥、w∥、wθ
+  pub fn set_weights(&mut self, perpendicular_weight: f64, parallel_weight: f64, angle_weight: f64) -> &mut Self {
+    self.distance_config.set_perpendicular_weight(perpendicular_weight);
+    self.distance_config.set_parallel_weight(parallel_weight);
+    self.distance_config.set_angle_weight(angle_weight);
+    self
+  }
+
+  /// 设置代表轨迹的平滑参数 γ
+  ///
+  /// 默认值 `DEFAULT_GAMMA` 由默认的最短线段长度 50 得到，
+  /// 修改最短线段长度后应当按相同比例调整 γ，否则代表轨迹的点会过密或过疏
+  pub fn set_gamma(&mut self, gamma: f64) -> &mut Self {
+    self.gamma = gamma;
+    self
+  }
+
+  /// 设置轨迹划分的配置
+  pub fn set_partition_config(&mut self, partition_config: PartitionConfig) -> &mut Self {
+    self.partition_config = partition_config;
+    self
+  }
+
+  /// 设置划分轨迹时的 MDL 代价优势
+  pub fn set_mdl_cost_advantage(&mut self, mdl_cost_advantage: usize) -> &mut Self {
+    self.partition_config.set_mdl_cost_advantage(mdl_cost_advantage);
+    self
+  }
+
+  /// 设置划分轨迹时的最短线段长度
+  pub fn set_min_line_segment_length(&mut self, min_line_segment_length: f64) -> &mut Self {
+    self.partition_config.set_min_line_segment_length(min_line_segment_length);
+    self
+  }
+
+  /// 设置聚类使用的线程数，None 表示使用全局线程池
+  pub fn set_num_threads(&mut self, num_threads: Option<usize>) -> &mut Self {
+    self.num_threads = num_threads;
+    self
+  }
+
+  /// 获得线段距离的配置
+  pub fn get_distance_config(&self) -> &DistanceConfig {
+    &self.distance_config
+  }
+
+  /// 获得轨迹划分的配置
+  pub fn get_partition_config(&self) -> &PartitionConfig {
+    &self.partition_config
+  }
+
+  /// 对轨迹执行完整的 TraClus
+  pub fn run(&self, trajectories: Vec<Trajectory>) -> Result<ClusteringResult, TraClusError> {
+    self.validate()?;
+    self.partition_config.validate().map_err(TraClusError::InvalidParameter)?;
+
+    let num_of_trajectories = trajectories.len();
+
+    // 划分轨迹
+    let thick_trajectories = partition_trajectories(trajectories, &self.distance_config, &self.partition_config);
+    let line_segments = get_partition_line(&thick_trajectories, &self.distance_config, &self.partition_config);
+
+    let mut result = self.cluster(line_segments)?;
+    result.thick_trajectories = thick_trajectories;
+    result.statistics.num_of_trajectories = num_of_trajectories;
+
+    Ok(result)
+  }
+
+  /// 跳过轨迹划分，直接对已有的线段聚类，例如重新聚类之前保存的线段
+  ///
+  /// 结果中没有划分后的轨迹，轨迹数量为线段所属的不同轨迹的数量
+  pub fn cluster_line_segments(&self, line_segments: Vec<LineSegment>) -> Result<ClusteringResult, TraClusError> {
+    self.validate()?;
+
+    let mut result = self.cluster(line_segments)?;
+    result.statistics.num_of_trajectories = result.line_segments.iter()
+      .map(|line_segment| (line_segment.get_trajectory_id(), line_segment.get_part()))
+      .collect::<HashSet<(usize, usize)>>()
+      .len();
+
+    Ok(result)
+  }
+
+  /// 检查参数是否有效
+  fn validate(&self) -> Result<(), TraClusError> {
+    if !self.eps.is_finite() || self.eps < 0.0 { return Err(TraClusError::InvalidParameter("eps")); }
+    if self.min_lns == 0 { return Err(TraClusError::InvalidParameter("min_lns")); }
+    if !self.gamma.is_finite() || self.gamma < 0.0 { return Err(TraClusError::InvalidParameter("gamma")); }
+    self.distance_config.validate().map_err(TraClusError::InvalidParameter)?;
+
+    Ok(())
+  }
+
+  /// 对线段执行 DBSCAN 并生成代表轨迹，结果中的划分后轨迹为空、轨迹数量为 0
+  fn cluster(&self, line_segments: Vec<LineSegment>) -> Result<ClusteringResult, TraClusError> {
+    let min_trajectories = self.min_trajectories.unwrap_or(self.min_lns);
+
+    // 执行聚类
+    let (cluster_indexs, cluster_index) = match self.num_threads {
+      Some(num_threads) => perform_dbscan_with_threads(num_threads, self.eps, self.min_lns, min_trajectories,
+        &line_segments, &self.distance_config).map_err(TraClusError::ThreadPool)?,
+      None => perform_dbscan(self.eps, self.min_lns, min_trajectories, &line_segments, &self.distance_config)
+    };
+
+    // 构建聚类
+    let line_segment_clusters = construct_line_segment_cluster_with_gamma(cluster_index, self.min_lns, self.gamma,
+      &cluster_indexs, &line_segments, &self.distance_config);
+    let clusters = construct_cluster(line_segment_clusters);
+
+    let statistics = ClusteringStatistics {
+      num_of_trajectories: 0,
+      num_of_line_segments: line_segments.len(),
+      num_of_segment_clusters: cluster_index,
+      num_of_clusters: clusters.len(),
+      num_of_noise: cluster_indexs.iter().filter(|label| **label < 0).count()
+    };
+
+    Ok(ClusteringResult {
+      clusters,
+      cluster_indexs,
+      line_segments,
+      thick_trajectories: Vec::new(),
+      statistics
+    })
+  }
+}
+
+/// 聚类过程的统计信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClusteringStatistics {
+  num_of_trajectories: usize,
+  num_of_line_segments: usize,
+  num_of_segment_clusters: usize,
+  num_of_clusters: usize,
+  num_of_noise: usize,
+}
+
+impl ClusteringStatistics {
+  /// 获得输入轨迹的数量
+  pub fn get_num_of_trajectories(&self) -> usize {
+    self.num_of_trajectories
+  }
+
+  /// 获得划分后线段的数量
+  pub fn get_num_of_line_segments(&self) -> usize {
+    self.num_of_line_segments
+  }
+
+  /// 获得 DBSCAN 给出的簇的数量
+  pub fn get_num_of_segment_clusters(&self) -> usize {
+    self.num_of_segment_clusters
+  }
+
+  /// 获得最终生成的簇的数量
+  pub fn get_num_of_clusters(&self) -> usize {
+    self.num_of_clusters
+  }
+
+  /// 获得噪声线段的数量
+  pub fn get_num_of_noise(&self) -> usize {
+    self.num_of_noise
+  }
+
+  /// 获得噪声线段占所有线段的比例，没有线段时为 0
+  pub fn get_noise_ratio(&self) -> f64 {
+    if self.num_of_line_segments == 0 { return 0.0; }
+
+    self.num_of_noise as f64 / self.num_of_line_segments as f64
+  }
+}
+
+/// TraClus 的结果
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClusteringResult {
+  clusters: Vec<Cluster>,
+  cluster_indexs: Vec<i32>,
+  line_segments: Vec<LineSegment>,
+  thick_trajectories: Vec<ThickTrajectory>,
+  statistics: ClusteringStatistics,
+}
+
+impl ClusteringResult {
+  /// 获得所有簇及其代表轨迹
+  pub fn get_clusters(&self) -> &Vec<Cluster> {
+    &self.clusters
+  }
+
+  /// 获得所有簇的所有权
+  pub fn into_clusters(self) -> Vec<Cluster> {
+    self.clusters
+  }
+
+  /// 获得每条线段的簇索引，噪声为 -1，顺序与 `get_line_segments` 一致
+  pub fn get_cluster_indexs(&self) -> &Vec<i32> {
+    &self.cluster_indexs
+  }
+
+  /// 获得划分后的所有线段
+  pub fn get_line_segments(&self) -> &Vec<LineSegment> {
+    &self.line_segments
+  }
+
+  /// 获得划分后的所有线段的所有权，可以保存下来之后用 `TraClus::cluster_line_segments` 重新聚类
+  pub fn into_line_segments(self) -> Vec<LineSegment> {
+    self.line_segments
+  }
+
+  /// 获得划分后的轨迹，即每条轨迹的划分点，直接对线段聚类时为空
+  pub fn get_thick_trajectories(&self) -> &Vec<ThickTrajectory> {
+    &self.thick_trajectories
+  }
+
+  /// 获得统计信息
+  pub fn get_statistics(&self) -> &ClusteringStatistics {
+    &self.statistics
+  }
+}
@@ -1,102 +1,370 @@
 use crate::{
   models::{
-    merge_indexs::MergeIndexs,
+    segment_index::SegmentIndex,
     line_segment::LineSegment
   },
   distance_util::{
-    measure_distance_line_to_line
-  }
+    measure_distance_line_to_line,
+    compute_search_radius
+  },
+  distance_config::DistanceConfig
 };
-use rayon::ThreadPoolBuilder;
+use rayon::{ThreadPool, ThreadPoolBuilder, ThreadPoolBuildError};
 use rayon::prelude::*;
-use uuid::Uuid;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex, RwLock};
+use std::collections::{HashSet, VecDeque};
 
 static NOISE: i32 = -1;
+static UNCLASSIFIED: i32 = -2;
 
-pub fn perform_dbscan(eps: f64, min_lns: usize, line_segments: &Vec<LineSegment>) -> (Vec<i32>, usize) {
-  let pool = ThreadPoolBuilder::new().num_threads(16).build().unwrap();
+/// 在全局线程池中对线段执行 DBSCAN，返回每条线段的簇索引（噪声为 -1）以及簇的数量
+///
+/// 结果与论文中逐个扩展簇的 DBSCAN 一致：簇的序号按照簇内最小的核心线段序号排列，
+/// 可以被多个簇到达的边界线段属于序号最小的簇，因此每次运行的结果都相同。
+///
+/// 参与轨迹数 |PTR(C)| 少于 `min_trajectories` 的簇会被舍弃，其中的线段标记为噪声，
+/// 其余的簇按照原来的顺序重新编号。论文中该阈值与 MinLns 相同。
+///
+/// 邻域查询通过空间索引只计算可能在 eps 范围内的线段，
+/// 无法确定搜索半径时退化为与所有线段比较，两者的结果相同
+pub fn perform_dbscan(eps: f64, min_lns: usize, min_trajectories: usize, line_segments: &[LineSegment],
+  config: &DistanceConfig) -> (Vec<i32>, usize)
+{
+  dbscan(eps, min_lns, min_trajectories, line_segments, config)
+}
 
-  let len = line_segments.len();
+/// 在指定的线程池中对线段执行 DBSCAN
+pub fn perform_dbscan_in_pool(pool: &ThreadPool, eps: f64, min_lns: usize, min_trajectories: usize,
+  line_segments: &[LineSegment], config: &DistanceConfig) -> (Vec<i32>, usize)
+{
+  pool.install(|| dbscan(eps, min_lns, min_trajectories, line_segments, config))
+}
 
-  let merge_indexs = MergeIndexs::new();
-  let merge_indexs = Arc::new(Mutex::new(merge_indexs));
-  let core_uuids: HashSet<&Uuid> = HashSet::new();
-  let core_uuids = Arc::new(RwLock::new(core_uuids));
+/// 创建一个有 `num_threads` 个线程的线程池执行 DBSCAN，线程池无法创建时返回错误
+pub fn perform_dbscan_with_threads(num_threads: usize, eps: f64, min_lns: usize, min_trajectories: usize,
+  line_segments: &[LineSegment], config: &DistanceConfig) -> Result<(Vec<i32>, usize), ThreadPoolBuildError>
+{
+  let pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
 
-  for i in 0..len {
-    let line_1 = line_segments.get(i).unwrap();
-    let (line_1_start, line_1_end) = line_1.extract_start_end_points();
-    let iter_of_line_segments = line_segments.iter().enumerate();
+  Ok(perform_dbscan_in_pool(&pool, eps, min_lns, min_trajectories, line_segments, config))
+}
 
-    let clone_merge_indexs = Arc::clone(&merge_indexs);
-    let clone_core_uuids = Arc::clone(&core_uuids);
+/// 在当前的线程池中执行 DBSCAN
+fn dbscan(eps: f64, min_lns: usize, min_trajectories: usize, line_segments: &[LineSegment],
+  config: &DistanceConfig) -> (Vec<i32>, usize)
+{
+  let neighbours: Vec<Vec<usize>> = find_neighbours(eps, line_segments, config)
+    .into_iter()
+    .map(|neighbour| neighbour.into_iter().map(|(index, _)| index).collect())
+    .collect();
 
-    let is_core = pool.install(|| {
-      let mut cluster_size: usize = 0;
-      let mut can_merge_index: Vec<usize> = Vec::new();
+  expand_clusters(&neighbours, min_lns, min_trajectories, line_segments)
+}
+
+/// 并行计算每条线段的 eps 邻域，返回邻域中每条线段的序号以及到它的距离
+///
+/// 邻域包含线段自身，按照线段序号升序排列
+pub(crate) fn find_neighbours(eps: f64, line_segments: &[LineSegment],
+  config: &DistanceConfig) -> Vec<Vec<(usize, f64)>>
+{
+  let segment_index = SegmentIndex::new(line_segments, compute_search_radius(eps, config),
+    config.get_coordinate_mode());
+
+  (0..line_segments.len()).into_par_iter()
+    .map(|i| {
+      let (line_1_start, line_1_end) = line_segments.get(i).unwrap().extract_start_end_points();
+      segment_index.query(i)
+        .into_iter()
+        .map(|index| {
+          let (line_2_start, line_2_end) = line_segments.get(index).unwrap().extract_start_end_points();
+          (index, measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end, config))
+        })
+        .filter(|(_, distance)| *distance <= eps)
+        .collect()
+    })
+    .collect()
+}
 
-      for (index, line_2) in iter_of_line_segments {
-        let (line_2_start, line_2_end) = line_2.extract_start_end_points();
-        if measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end) <= eps {
-          cluster_size += 1;
+/// 根据每条线段的邻域扩展簇，返回每条线段的簇索引以及簇的数量
+pub(crate) fn expand_clusters(neighbours: &[Vec<usize>], min_lns: usize, min_trajectories: usize,
+  line_segments: &[LineSegment]) -> (Vec<i32>, usize)
+{
+  // 按照线段序号依次从尚未分类的核心线段出发扩展簇，
+  // 因此簇的序号按照簇内最小的核心线段序号排列，边界线段属于最先到达它的簇
+  let mut result: Vec<i32> = vec![UNCLASSIFIED; line_segments.len()];
+  let mut cluster_index: usize = 0;
+  let mut queue: VecDeque<usize> = VecDeque::new();
 
-          if clone_core_uuids.read().unwrap().contains(line_2.get_uuid()) {
-            can_merge_index.push(index);
-          }
+  for i in 0..line_segments.len() {
+    if result[i] != UNCLASSIFIED { continue; }
+    if neighbours[i].len() < min_lns {
+      result[i] = NOISE;
+      continue;
+    }
+
+    // ExpandCluster
+    result[i] = cluster_index as i32;
+    queue.push_back(i);
+    while let Some(seed) = queue.pop_front() {
+      if neighbours[seed].len() < min_lns { continue; }
+
+      for index in neighbours[seed].iter() {
+        if result[*index] == UNCLASSIFIED {
+          queue.push_back(*index);
+        }
+        if result[*index] == UNCLASSIFIED || result[*index] == NOISE {
+          result[*index] = cluster_index as i32;
         }
       }
+    }
 
-      if cluster_size >= min_lns {
-        if can_merge_index.len() > 0 {
-          clone_merge_indexs.lock().unwrap().set_to_min(&can_merge_index, i);
-        } else {
-          clone_merge_indexs.lock().unwrap().push(i);
-        }
+    cluster_index += 1;
+  }
 
-        return true;
-      }
+  filter_by_trajectory_cardinality(result, cluster_index, min_trajectories, line_segments)
+}
 
-      false
-    });
+/// 将参与轨迹数少于 `min_trajectories` 的簇标记为噪声，并将其余的簇重新编号
+fn filter_by_trajectory_cardinality(mut result: Vec<i32>, num_of_clusters: usize,
+  min_trajectories: usize, line_segments: &[LineSegment]) -> (Vec<i32>, usize)
+{
+  let mut trajectory_ids: Vec<HashSet<usize>> = vec![HashSet::new(); num_of_clusters];
+  for (index, line_segment) in line_segments.iter().enumerate() {
+    if result[index] >= 0 {
+      trajectory_ids[result[index] as usize].insert(line_segment.get_trajectory_id());
+    }
+  }
 
-    if is_core {
-      core_uuids.write().unwrap().insert(line_1.get_uuid());
+  // 旧的簇序号到新的簇序号的映射
+  let mut new_indexs: Vec<i32> = Vec::with_capacity(num_of_clusters);
+  let mut num_of_kept: usize = 0;
+  for ids in trajectory_ids.iter() {
+    if ids.len() >= min_trajectories {
+      new_indexs.push(num_of_kept as i32);
+      num_of_kept += 1;
+    } else {
+      new_indexs.push(NOISE);
     }
   }
 
-  merge_indexs.lock().unwrap().correct_indexs();
-  let merge_cluster_indexs = merge_indexs.lock().unwrap().map_indexs();
+  for label in result.iter_mut() {
+    if *label >= 0 {
+      *label = new_indexs[*label as usize];
+    }
+  }
 
-  let mut result: Vec<i32> = vec![NOISE; line_segments.len()];
-  
+  (result, num_of_kept)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    models::{
+      point::Point,
+      line_segment::SegmentId
+    },
+    distance_config::{AngleMode, CoordinateMode}
+  };
 
-  for (index, merge_cluster_index) in merge_cluster_indexs.iter().enumerate() {
-    let mut core_segements: Vec<&LineSegment> = Vec::new();
+  /// 简单的线性同余随机数，保证每次生成的线段相同
+  struct Lcg(u64);
 
-    for index in merge_cluster_index {
-      core_segements.push(line_segments.get(*index).unwrap());
+  impl Lcg {
+    fn next(&mut self) -> f64 {
+      self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+      (self.0 >> 11) as f64 / (1u64 << 53) as f64
     }
+  }
+
+  /// 在 [x0, x0 + width] × [y0, y0 + height] 中随机生成线段，长度不超过 `max_length`
+  fn random_segments(n: usize, origin: (f64, f64), size: (f64, f64), max_length: f64, seed: u64) -> Vec<LineSegment> {
+    let mut lcg = Lcg(seed);
+    (0..n)
+      .map(|i| {
+        let start = Point::new(origin.0 + lcg.next() * size.0, origin.1 + lcg.next() * size.1);
+        let end = Point::new(start.get_x() + (lcg.next() - 0.5) * max_length,
+          start.get_y() + (lcg.next() - 0.5) * max_length);
+        LineSegment::new(SegmentId::new(i % 7, 0, i), (0, 1), start, end)
+      })
+      .collect()
+  }
+
+  /// 不使用空间索引，与所有线段比较得到的邻域
+  fn brute_force_neighbours(eps: f64, line_segments: &[LineSegment], config: &DistanceConfig) -> Vec<Vec<(usize, f64)>> {
+    line_segments.iter()
+      .map(|line_1| {
+        let (line_1_start, line_1_end) = line_1.extract_start_end_points();
+        line_segments.iter()
+          .enumerate()
+          .map(|(index, line_2)| {
+            let (line_2_start, line_2_end) = line_2.extract_start_end_points();
+            (index, measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end, config))
+          })
+          .filter(|(_, distance)| *distance <= eps)
+          .collect()
+      })
+      .collect()
+  }
+
+  fn assert_same_neighbours(eps_values: &[f64], line_segments: &[LineSegment], config: &DistanceConfig) {
+    for eps in eps_values {
+      let expected = brute_force_neighbours(*eps, line_segments, config);
+      assert_eq!(find_neighbours(*eps, line_segments, config), expected, "eps {} config {:?}", eps, config);
+    }
+  }
 
-    let others: Vec<usize> = line_segments.par_iter()
+  /// 按照轨迹 id 创建互不相同的线段，几何形状与依赖手工邻域的测试无关
+  fn segments_of(trajectory_ids: &[usize]) -> Vec<LineSegment> {
+    trajectory_ids.iter()
       .enumerate()
-      .filter_map(|(i, line_segment)| {
-        let (line_1_start, line_1_end) = line_segment.extract_start_end_points();
-        for core_segement in core_segements.iter() {
-          let (line_2_start, line_2_end) = core_segement.extract_start_end_points();
-          if measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end) <= eps {
-            return Some(i);
-          }
-        }
-        return None;
+      .map(|(i, trajectory_id)| {
+        let start = Point::new(i as f64 * 100.0, 0.0);
+        let end = Point::new(i as f64 * 100.0 + 60.0, 0.0);
+        LineSegment::new(SegmentId::new(*trajectory_id, 0, i), (0, 1), start, end)
       })
-      .collect();
+      .collect()
+  }
+
+  #[test]
+  fn clusters_are_ordered_by_lowest_core_index() {
+    // MinLns 为 4，核心线段为 1、2、4，其余为边界线段或噪声
+    let neighbours: Vec<Vec<usize>> = vec![
+      vec![0, 1],
+      vec![0, 1, 2, 7],
+      vec![1, 2, 5, 7],
+      vec![3],
+      vec![4, 5, 6, 8],
+      vec![2, 4, 5],
+      vec![4, 6, 8],
+      vec![1, 2, 7],
+      vec![4, 6, 8]
+    ];
+    let line_segments = segments_of(&(0..neighbours.len()).collect::<Vec<usize>>());
+
+    let (result, num_of_clusters) = expand_clusters(&neighbours, 4, 1, &line_segments);
+
+    // 线段 0 先被标记为噪声，之后被簇 0 到达成为边界线段；
+    // 线段 5 同时与簇 0 的核心线段 2 和簇 1 的核心线段 4 相邻，属于先到达它的簇 0
+    assert_eq!(result, vec![0, 0, 0, -1, 1, 0, 1, 0, 1]);
+    assert_eq!(num_of_clusters, 2);
+  }
+
+  #[test]
+  fn border_segment_goes_to_the_first_cluster() {
+    // MinLns 为 4，核心线段为 0 和 4，线段 2 是两个簇共同的边界线段，
+    // 即使它的邻域中先出现簇 1 的核心线段，也属于先到达它的簇 0
+    let neighbours: Vec<Vec<usize>> = vec![
+      vec![0, 1, 2, 5],
+      vec![0, 1, 5],
+      vec![4, 2, 0],
+      vec![3, 4, 6],
+      vec![2, 3, 4, 6],
+      vec![0, 1, 5],
+      vec![3, 4, 6]
+    ];
+    let line_segments = segments_of(&[0, 1, 2, 3, 4, 5, 6]);
+
+    let (result, num_of_clusters) = expand_clusters(&neighbours, 4, 1, &line_segments);
+
+    assert_eq!(result, vec![0, 0, 0, 1, 1, 0, 1]);
+    assert_eq!(num_of_clusters, 2);
+  }
+
+  #[test]
+  fn single_trajectory_cluster_becomes_noise() {
+    // 簇 0 的线段都来自轨迹 5，被标记为噪声后簇 1、2 重新编号为 0、1
+    let result = vec![0, 1, 0, -1, 2, 1, 2, 0];
+    let line_segments = segments_of(&[5, 1, 5, 3, 2, 4, 3, 5]);
+
+    let (result, num_of_clusters) = filter_by_trajectory_cardinality(result, 3, 2, &line_segments);
+
+    assert_eq!(result, vec![-1, 0, -1, -1, 1, 0, 1, -1]);
+    assert_eq!(num_of_clusters, 2);
+  }
+
+  #[test]
+  fn cardinality_is_counted_by_trajectory() {
+    // MinLns 为 3，得到三个簇，其中簇 1 的三条线段都来自轨迹 5
+    let neighbours: Vec<Vec<usize>> = vec![
+      vec![0, 1, 2],
+      vec![0, 1, 2],
+      vec![0, 1, 2],
+      vec![3, 4, 5],
+      vec![3, 4, 5],
+      vec![3, 4, 5],
+      vec![6, 7, 8],
+      vec![6, 7, 8],
+      vec![6, 7, 8]
+    ];
+    let line_segments = segments_of(&[0, 1, 2, 5, 5, 5, 3, 4, 3]);
+
+    assert_eq!(expand_clusters(&neighbours, 3, 1, &line_segments), (vec![0, 0, 0, 1, 1, 1, 2, 2, 2], 3));
+
+    let (result, num_of_clusters) = expand_clusters(&neighbours, 3, 2, &line_segments);
 
-    for other in others {
-      result[other] = index as i32;
+    assert_eq!(result, vec![0, 0, 0, -1, -1, -1, 1, 1, 1]);
+    assert_eq!(num_of_clusters, 2);
+
+    // 簇 2 只有两条轨迹，提高阈值后也成为噪声
+    let (result, num_of_clusters) = expand_clusters(&neighbours, 3, 3, &line_segments);
+
+    assert_eq!(result, vec![0, 0, 0, -1, -1, -1, -1, -1, -1]);
+    assert_eq!(num_of_clusters, 1);
+  }
+
+  #[test]
+  fn perform_dbscan_labels_groups_in_index_order() {
+    // 两组平行线段交错排列，第一条线段所在的组为簇 0
+    let line_segment = |i: usize, y: f64| {
+      LineSegment::new(SegmentId::new(i, 0, 0), (0, 1), Point::new(0.0, y), Point::new(100.0, y))
+    };
+    let line_segments = vec![
+      line_segment(0, 1000.0), line_segment(1, 0.0), line_segment(2, 1005.0),
+      line_segment(3, 5.0), line_segment(4, 1010.0), line_segment(5, 10.0),
+      line_segment(6, 5000.0)
+    ];
+
+    let (result, num_of_clusters) = perform_dbscan(20.0, 3, 3, &line_segments, &DistanceConfig::default());
+
+    assert_eq!(result, vec![0, 1, 0, 1, 0, 1, -1]);
+    assert_eq!(num_of_clusters, 2);
+  }
+
+  #[test]
+  fn planar_index_matches_brute_force() {
+    let line_segments = random_segments(200, (0.0, 0.0), (500.0, 500.0), 120.0, 1);
+    let weights = [(1.0, 1.0, 1.0), (0.2, 1.0, 1.0), (1.0, 0.1, 3.0), (5.0, 5.0, 0.0), (1.0, 0.0, 1.0)];
+
+    for (perpendicular_weight, parallel_weight, angle_weight) in weights.iter() {
+      for angle_mode in &[AngleMode::Directed, AngleMode::Undirected] {
+        let mut config = DistanceConfig::new(*perpendicular_weight, *parallel_weight, *angle_weight);
+        config.set_angle_mode(*angle_mode);
+        assert_same_neighbours(&[0.0, 15.0, 60.0], &line_segments, &config);
+      }
+    }
+  }
+
+  #[test]
+  fn geographic_index_matches_brute_force() {
+    let mut config = DistanceConfig::default();
+    config.set_coordinate_mode(CoordinateMode::Geographic);
+
+    // 高纬度、跨越 180° 经线以及跨越整个经度范围的数据
+    let cases = [
+      random_segments(200, (10.0, 60.0), (0.3, 0.2), 0.02, 2),
+      random_segments(200, (179.7, -45.0), (0.6, 0.3), 0.02, 3),
+      random_segments(200, (-180.0, -70.0), (360.0, 140.0), 2.0, 4)
+    ];
+    for line_segments in cases.iter() {
+      assert_same_neighbours(&[100.0, 1000.0, 5000.0], line_segments, &config);
     }
   }
 
-  (result, merge_cluster_indexs.len())
+  #[test]
+  fn geographic_index_prunes_candidates() {
+    let line_segments = random_segments(300, (179.6, 60.0), (0.8, 0.4), 0.02, 5);
+    let segment_index = SegmentIndex::new(&line_segments, Some(500.0), CoordinateMode::Geographic);
+
+    assert!(segment_index.query(0).len() < line_segments.len() / 4);
+  }
 }
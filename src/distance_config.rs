@@ -0,0 +1,115 @@
+//! 线段距离的配置
+//!
+//! 线段距离为 `w⊥·d⊥ + w∥·d∥ + wθ·dθ`，三个权重默认都是 1。
+//! 角度距离默认不考虑方向，需要区分相反方向的流动时使用 `AngleMode::Directed`。
+//! 例如对于道路类的数据可以降低平行距离的权重，对于航线可以提高角度距离的权重。
+
+/// 角度距离的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleMode {
+  // 考虑方向，夹角不小于 90° 时角度距离为短线段的长度，即论文中的定义
+  Directed,
+  // 不考虑方向，角度距离总是短线段的长度乘以 sinθ，方向相反的线段角度距离为 0
+  Undirected,
+}
+
+/// 坐标的含义
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateMode {
+  // 平面直角坐标，距离为欧氏距离
+  Planar,
+  // WGS84 经纬度，x 为经度、y 为纬度，单位为度，可选的 z 为高程，单位为米，
+  // 距离在局部切平面上计算，单位为米
+  Geographic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceConfig {
+  perpendicular_weight: f64,
+  parallel_weight: f64,
+  angle_weight: f64,
+  angle_mode: AngleMode,
+  coordinate_mode: CoordinateMode
+}
+
+impl Default for DistanceConfig {
+  fn default() -> Self {
+    Self::new(1.0, 1.0, 1.0)
+  }
+}
+
+impl DistanceConfig {
+  /// 创建一个距离配置
+  pub fn new(perpendicular_weight: f64, parallel_weight: f64, angle_weight: f64) -> Self {
+    Self {
+      perpendicular_weight,
+      parallel_weight,
+      angle_weight,
+      angle_mode: AngleMode::Undirected,
+      coordinate_mode: CoordinateMode::Planar
+    }
+  }
+
+  /// 获得垂直距离的权重 w⊥
+  pub fn get_perpendicular_weight(&self) -> f64 {
+    self.perpendicular_weight
+  }
+
+  /// 获得平行距离的权重 w∥
+  pub fn get_parallel_weight(&self) -> f64 {
+    self.parallel_weight
+  }
+
+  /// 获得角度距离的权重 wθ
+  pub fn get_angle_weight(&self) -> f64 {
+    self.angle_weight
+  }
+
+  /// 获得角度距离的计算方式
+  pub fn get_angle_mode(&self) -> AngleMode {
+    self.angle_mode
+  }
+
+  /// 设置角度距离的计算方式
+  pub fn set_angle_mode(&mut self, angle_mode: AngleMode) {
+    self.angle_mode = angle_mode;
+  }
+
+  /// 获得坐标的含义
+  pub fn get_coordinate_mode(&self) -> CoordinateMode {
+    self.coordinate_mode
+  }
+
+  /// 设置坐标的含义，经纬度坐标下 eps 与最短线段长度的单位都是米
+  pub fn set_coordinate_mode(&mut self, coordinate_mode: CoordinateMode) {
+    self.coordinate_mode = coordinate_mode;
+  }
+
+  /// 设置垂直距离的权重 w⊥
+  pub fn set_perpendicular_weight(&mut self, weight: f64) {
+    self.perpendicular_weight = weight;
+  }
+
+  /// 设置平行距离的权重 w∥
+  pub fn set_parallel_weight(&mut self, weight: f64) {
+    self.parallel_weight = weight;
+  }
+
+  /// 设置角度距离的权重 wθ
+  pub fn set_angle_weight(&mut self, weight: f64) {
+    self.angle_weight = weight;
+  }
+
+  /// 检查配置是否有效，无效时返回参数名
+  ///
+  /// 权重必须是有限的非负数，否则线段距离不再是非负的，空间索引的搜索半径也不再成立
+  pub fn validate(&self) -> Result<(), &'static str> {
+    let is_valid = |weight: f64| weight.is_finite() && weight >= 0.0;
+
+    if !is_valid(self.perpendicular_weight) { return Err("perpendicular_weight"); }
+    if !is_valid(self.parallel_weight) { return Err("parallel_weight"); }
+    if !is_valid(self.angle_weight) { return Err("angle_weight"); }
+
+    Ok(())
+  }
+}
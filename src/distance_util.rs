@@ -1,19 +1,125 @@
 use crate::{
   models::{
-    point::Point
+    point::{Point, MAX_DIMENSION}
+  },
+  distance_config::{
+    DistanceConfig,
+    AngleMode,
+    CoordinateMode
   }
 };
 
+/// 地球平均半径，单位为米
+pub(crate) static EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// 按照配置中的坐标含义计算一个点到一个点的距离
+///
+/// 经纬度坐标下使用 haversine 公式计算大圆距离，单位为米
+pub fn measure_distance(lp: &Point, rp: &Point, config: &DistanceConfig) -> f64 {
+  match config.get_coordinate_mode() {
+    CoordinateMode::Planar => measure_distance_point_to_point(lp, rp),
+    CoordinateMode::Geographic => {
+      let lat_1 = lp.get_y().to_radians();
+      let lat_2 = rp.get_y().to_radians();
+      let diff_lat = lat_2 - lat_1;
+      let diff_lon = normalize_longitude(rp.get_x() - lp.get_x()).to_radians();
+
+      let h = (diff_lat / 2.0).sin().powi(2) + lat_1.cos() * lat_2.cos() * (diff_lon / 2.0).sin().powi(2);
+      let surface_distance = 2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin();
+      let diff_z = rp.get_coord(2) - lp.get_coord(2);
+
+      (surface_distance.powi(2) + diff_z.powi(2)).sqrt()
+    }
+  }
+}
+
+/// 将经度差规范到 [-180, 180) 之间
+fn normalize_longitude(diff_lon: f64) -> f64 {
+  (diff_lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// 计算若干经纬度点的中心，经度相对第一个点展开以处理跨越 180° 经线的情况
+pub fn compute_geographic_centroid(points: &[&Point]) -> Point {
+  let base_lon = points[0].get_x();
+  let mut centroid = Point::init();
+
+  for point in points {
+    let lon = base_lon + normalize_longitude(point.get_x() - base_lon);
+    centroid.set_x(centroid.get_x() + lon / points.len() as f64);
+    centroid.set_y(centroid.get_y() + point.get_y() / points.len() as f64);
+  }
+
+  centroid
+}
+
+/// 以 origin 为原点将经纬度点投影到局部切平面上，x 指向东、y 指向北，单位为米
+///
+/// 使用等距圆柱投影，在原点附近几十公里的范围内误差很小
+pub fn project_to_local_plane(point: &Point, origin: &Point) -> Point {
+  let mut local_point = *point;
+
+  local_point.set_x(EARTH_RADIUS * normalize_longitude(point.get_x() - origin.get_x()).to_radians()
+    * origin.get_y().to_radians().cos());
+  local_point.set_y(EARTH_RADIUS * (point.get_y() - origin.get_y()).to_radians());
+
+  local_point
+}
+
+/// 将局部切平面上的点还原为经纬度点，是 `project_to_local_plane` 的逆运算
+pub fn unproject_from_local_plane(local_point: &Point, origin: &Point) -> Point {
+  let mut point = *local_point;
+
+  let lon = origin.get_x() + (local_point.get_x() / (EARTH_RADIUS * origin.get_y().to_radians().cos())).to_degrees();
+  point.set_x(normalize_longitude(lon));
+  point.set_y(origin.get_y() + (local_point.get_y() / EARTH_RADIUS).to_degrees());
+
+  point
+}
+
+/// 将两条线段的端点转换为可以直接使用平面公式计算的坐标
+///
+/// 平面坐标下直接复制；经纬度坐标下投影到以四个端点中心为原点的局部切平面上
+pub fn localize_lines(line_1_start: &Point, line_1_end: &Point,
+  line_2_start: &Point, line_2_end: &Point, config: &DistanceConfig) -> [Point; 4]
+{
+  let points = [line_1_start, line_1_end, line_2_start, line_2_end];
+
+  match config.get_coordinate_mode() {
+    CoordinateMode::Planar => [*line_1_start, *line_1_end, *line_2_start, *line_2_end],
+    CoordinateMode::Geographic => {
+      let origin = compute_geographic_centroid(&points);
+      [
+        project_to_local_plane(line_1_start, &origin),
+        project_to_local_plane(line_1_end, &origin),
+        project_to_local_plane(line_2_start, &origin),
+        project_to_local_plane(line_2_end, &origin)
+      ]
+    }
+  }
+}
+
 /// 计算一个点到一个点的距离
 pub fn measure_distance_point_to_point(lp: &Point, rp: &Point) -> f64 {
   let mut square_sum = 0.0;
 
-  square_sum += (lp.get_x() - rp.get_x()).powi(2);
-  square_sum += (lp.get_y() - rp.get_y()).powi(2);
+  for i in 0..MAX_DIMENSION {
+    square_sum += (lp.get_coord(i) - rp.get_coord(i)).powi(2);
+  }
 
   square_sum.sqrt()
 }
 
+/// 计算从起点指向终点的向量
+pub fn compute_vector(start: &Point, end: &Point) -> Point {
+  let mut vector = Point::zero(start.get_dimension().max(end.get_dimension()));
+
+  for i in 0..vector.get_dimension() {
+    vector.set_coord(i, end.get_coord(i) - start.get_coord(i));
+  }
+
+  vector
+}
+
 /// 计算一个点到一条线段的距离
 pub fn measure_distance_point_to_line(point: &Point, line_start: &Point, line_end: &Point) -> f64 {
   let (_, project_point) = project_point_to_line(point, line_start, line_end);
@@ -21,9 +127,39 @@ pub fn measure_distance_point_to_line(point: &Point, line_start: &Point, line_en
   measure_distance_point_to_point(point, &project_point)
 }
 
-/// 计算一条线段到一条线段的距离
+/// 计算一条线段到一条线段的距离，三个距离分量按照配置中的权重加权求和
 pub fn measure_distance_line_to_line(line_1_start: &Point, line_1_end: &Point,
-  line_2_start: &Point, line_2_end: &Point) -> f64 
+  line_2_start: &Point, line_2_end: &Point, config: &DistanceConfig) -> f64 
+{
+  if config.get_coordinate_mode() == CoordinateMode::Geographic {
+    let [line_1_start, line_1_end, line_2_start, line_2_end] =
+      localize_lines(line_1_start, line_1_end, line_2_start, line_2_end, config);
+    return measure_planar_distance_line_to_line(&line_1_start, &line_1_end, &line_2_start, &line_2_end, config);
+  }
+
+  measure_planar_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end, config)
+}
+
+/// 计算线段距离不超过 eps 时两条线段之间欧氏距离的上界，用于空间索引的邻域查询
+///
+/// 设较短线段的某个端点 p 在较长线段上的投影为 q，离 q 较近的较长线段端点为 e，
+/// 则两条线段的欧氏距离不超过 |pe| <= |pq| + |qe|。
+/// 垂直距离不小于两个 |pq| 中较大者的一半，平行距离等于两个 |qe| 中的较小者，
+/// 因此欧氏距离不超过 2 * d⊥ + d∥ <= 线段距离 / min(w⊥ / 2, w∥)。
+/// 经纬度坐标下该上界是局部切平面上的欧氏距离，单位为米。
+///
+/// 任一权重为 0 时没有这样的上界，返回 None
+pub fn compute_search_radius(eps: f64, config: &DistanceConfig) -> Option<f64> {
+  let factor = (config.get_perpendicular_weight() / 2.0).min(config.get_parallel_weight());
+  if factor <= 0.0 || eps.is_nan() { return None; }
+
+  // 略微放大以免浮点误差漏掉边界上的线段
+  Some(eps.max(0.0) / factor * (1.0 + 1e-9) + 1e-9)
+}
+
+/// 在平面坐标下计算一条线段到一条线段的距离
+fn measure_planar_distance_line_to_line(line_1_start: &Point, line_1_end: &Point,
+  line_2_start: &Point, line_2_end: &Point, config: &DistanceConfig) -> f64
 {
   let length_1 = measure_distance_point_to_point(line_1_start, line_1_end);
   let length_2 = measure_distance_point_to_point(line_2_start, line_2_end);
@@ -34,9 +170,9 @@ pub fn measure_distance_line_to_line(line_1_start: &Point, line_1_end: &Point,
     measure_perpendicular_distance(line_2_start, line_2_end, line_1_start, line_1_end)
   };
   let angle_distance = if length_1 > length_2 {
-    measure_angle_distance(line_1_start, line_1_end, line_2_start, line_2_end)
+    measure_angle_distance(line_1_start, line_1_end, line_2_start, line_2_end, config.get_angle_mode())
   } else {
-    measure_angle_distance(line_2_start, line_2_end, line_1_start, line_1_end)
+    measure_angle_distance(line_2_start, line_2_end, line_1_start, line_1_end, config.get_angle_mode())
   };
   let parallel_distance = if length_1 > length_2 {
     measure_parallel_distance(line_1_start, line_1_end, line_2_start, line_2_end)
@@ -44,7 +180,9 @@ pub fn measure_distance_line_to_line(line_1_start: &Point, line_1_end: &Point,
     measure_parallel_distance(line_2_start, line_2_end, line_1_start, line_1_end)
   };
 
-  parallel_distance + perpendicular_distance + angle_distance
+  config.get_parallel_weight() * parallel_distance
+    + config.get_perpendicular_weight() * perpendicular_distance
+    + config.get_angle_weight() * angle_distance
 }
 
 /// 计算两条线段的垂直距离
@@ -62,17 +200,13 @@ pub fn measure_perpendicular_distance(line_1_start: &Point, line_1_end: &Point,
 
 /// 计算两条线段的角度距离
 /// 注意，默认第一条线段比第二条线段长
+///
+/// 考虑方向时，夹角不小于 90° 的角度距离为第二条线段的长度
 pub fn measure_angle_distance(line_1_start: &Point, line_1_end: &Point,
-  line_2_start: &Point, line_2_end: &Point) -> f64 
+  line_2_start: &Point, line_2_end: &Point, angle_mode: AngleMode) -> f64 
 {
-  let vector_1 = Point::new(
-    line_1_end.get_x() - line_1_start.get_x(),
-    line_1_end.get_y() - line_1_start.get_y()
-  );
-  let vector_2 = Point::new(
-    line_2_end.get_x() - line_2_start.get_x(),
-    line_2_end.get_y() - line_2_start.get_y()
-  );
+  let vector_1 = compute_vector(line_1_start, line_1_end);
+  let vector_2 = compute_vector(line_2_start, line_2_end);
 
   let vector_1_length = compute_vector_length(&vector_1);
   let vector_2_length = compute_vector_length(&vector_2);
@@ -80,8 +214,9 @@ pub fn measure_angle_distance(line_1_start: &Point, line_1_end: &Point,
   if vector_1_length == 0.0 || vector_2_length == 0.0 { return 0.0; }
 
   let inner_product = compute_inner_product(&vector_1, &vector_2);
-  let mut cos_theta = inner_product / (vector_1_length * vector_2_length);
-  cos_theta = if cos_theta > 1.0 { 1.0 } else if cos_theta < -1.0 { -1.0 } else { cos_theta };
+  let cos_theta = (inner_product / (vector_1_length * vector_2_length)).clamp(-1.0, 1.0);
+  if angle_mode == AngleMode::Directed && cos_theta <= 0.0 { return vector_2_length; }
+
   let sin_theta = (1.0 - cos_theta.powi(2)).sqrt();
 
   vector_2_length * sin_theta
@@ -113,40 +248,30 @@ pub fn measure_parallel_distance(line_1_start: &Point, line_1_end: &Point,
 pub fn compute_inner_product(vector_1: &Point, vector_2: &Point) -> f64 {
   let mut inner_product = 0.0;
 
-  inner_product += vector_1.get_x() * vector_2.get_x();
-  inner_product += vector_1.get_y() * vector_2.get_y();
+  for i in 0..MAX_DIMENSION {
+    inner_product += vector_1.get_coord(i) * vector_2.get_coord(i);
+  }
 
   inner_product
 }
 
 /// 计算一个向量的长度
 pub fn compute_vector_length(vector: &Point) -> f64 {
-  let mut square_sum = 0.0;
-
-  square_sum += vector.get_x().powi(2);
-  square_sum += vector.get_y().powi(2);
-
-  square_sum.sqrt()
+  compute_inner_product(vector, vector).sqrt()
 }
 
 // 获得一个点对于一条线段的投影点
 fn project_point_to_line(point: &Point, line_start: &Point, line_end: &Point) -> (f64, Point) {
-  let vector_1 = Point::new(
-    point.get_x() - line_start.get_x(),
-    point.get_y() - line_start.get_y()
-  );
-  let vector_2 = Point::new(
-    line_end.get_x() - line_start.get_x(),
-    line_end.get_y() - line_start.get_y()
-  );
+  let vector_1 = compute_vector(line_start, point);
+  let vector_2 = compute_vector(line_start, line_end);
 
   // 获得投影点的坐标
   // @BUG 出现了除以 0
   let cofficient = compute_inner_product(&vector_1, &vector_2) / compute_inner_product(&vector_2, &vector_2);
-  let project_point = Point::new(
-    line_start.get_x() + cofficient * vector_2.get_x(),
-    line_start.get_y() + cofficient * vector_2.get_y()
-  );
+  let mut project_point = Point::zero(point.get_dimension().max(vector_2.get_dimension()));
+  for i in 0..project_point.get_dimension() {
+    project_point.set_coord(i, line_start.get_coord(i) + cofficient * vector_2.get_coord(i));
+  }
 
   (cofficient, project_point)
 }
\ No newline at end of file
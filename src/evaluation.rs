@@ -0,0 +1,157 @@
+//! 聚类质量评估，按照论文中的 QMeasure 比较不同参数下的聚类结果
+//!
+//! 一组线段的 SSE 为组内所有线段对距离的平方和除以两倍的线段数量：
+//! SSE(C) = 1 / (2|C|) * Σ_{x∈C} Σ_{y∈C} dist(x, y)²。
+//! QMeasure = Σ SSE(Ci) + 噪声惩罚，噪声惩罚是所有噪声线段组成的组的 SSE。
+use crate::{
+  models::{
+    line_segment::LineSegment
+  },
+  distance_util::measure_distance_line_to_line,
+  distance_config::DistanceConfig
+};
+use rayon::prelude::*;
+
+/// 聚类的质量
+#[derive(Debug, Clone)]
+pub struct QualityMeasure {
+  cluster_sse: Vec<f64>,
+  noise_penalty: f64
+}
+
+impl QualityMeasure {
+  /// 获得每个簇的 SSE，下标为 DBSCAN 给出的簇索引
+  pub fn get_cluster_sse(&self) -> &Vec<f64> {
+    &self.cluster_sse
+  }
+
+  /// 获得所有簇的 SSE 之和
+  pub fn get_total_sse(&self) -> f64 {
+    self.cluster_sse.iter().sum()
+  }
+
+  /// 获得噪声惩罚
+  pub fn get_noise_penalty(&self) -> f64 {
+    self.noise_penalty
+  }
+
+  /// 获得 QMeasure，越小说明簇越紧凑、噪声越少
+  pub fn get_q_measure(&self) -> f64 {
+    self.get_total_sse() + self.noise_penalty
+  }
+}
+
+/// 根据 `perform_dbscan` 返回的线段标签评估聚类质量
+///
+/// `cluster_indexs` 中噪声为 -1，`num_of_clusters` 为簇的数量，线段距离按照 `config` 计算
+pub fn evaluate_clustering(line_segments: &[LineSegment], cluster_indexs: &[i32], num_of_clusters: usize,
+  config: &DistanceConfig) -> QualityMeasure
+{
+  let (clusters, noise) = group_by_label(cluster_indexs, num_of_clusters);
+
+  QualityMeasure {
+    cluster_sse: clusters.iter()
+      .map(|cluster| compute_sse(line_segments, cluster, config))
+      .collect(),
+    noise_penalty: compute_sse(line_segments, &noise, config)
+  }
+}
+
+/// 使用预先计算的线段距离评估聚类质量，结果与 `evaluate_clustering` 相同
+pub(crate) fn evaluate_clustering_with_distances(distances: &SquaredDistances, cluster_indexs: &[i32],
+  num_of_clusters: usize) -> QualityMeasure
+{
+  let (clusters, noise) = group_by_label(cluster_indexs, num_of_clusters);
+
+  QualityMeasure {
+    cluster_sse: clusters.iter()
+      .map(|cluster| distances.compute_sse(cluster))
+      .collect(),
+    noise_penalty: distances.compute_sse(&noise)
+  }
+}
+
+/// 按照标签将线段序号分组，返回每个簇的线段与噪声线段，组内的序号升序排列
+fn group_by_label(cluster_indexs: &[i32], num_of_clusters: usize) -> (Vec<Vec<usize>>, Vec<usize>) {
+  let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); num_of_clusters];
+  let mut noise: Vec<usize> = Vec::new();
+  for (index, label) in cluster_indexs.iter().enumerate() {
+    if *label >= 0 {
+      clusters[*label as usize].push(index);
+    } else {
+      noise.push(index);
+    }
+  }
+
+  (clusters, noise)
+}
+
+/// 计算一组线段的 SSE，空组为 0
+fn compute_sse(line_segments: &[LineSegment], group: &[usize], config: &DistanceConfig) -> f64 {
+  if group.is_empty() { return 0.0; }
+
+  let sum: f64 = group.par_iter()
+    .map(|i| {
+      let (line_1_start, line_1_end) = line_segments.get(*i).unwrap().extract_start_end_points();
+      group.iter()
+        .map(|j| {
+          let (line_2_start, line_2_end) = line_segments.get(*j).unwrap().extract_start_end_points();
+          measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end, config).powi(2)
+        })
+        .sum::<f64>()
+    })
+    .sum();
+
+  sum / (2.0 * group.len() as f64)
+}
+
+/// 所有线段对之间距离的平方，供参数扫描在不同的 (eps, MinLns) 之间复用
+///
+/// 线段距离并不对称，第 i 条与第 j 条线段（i < j）保存两个方向的距离平方之和，
+/// 线段与自身保存一个方向，共 n(n+1)/2 个值
+pub(crate) struct SquaredDistances {
+  len: usize,
+  values: Vec<f64>
+}
+
+impl SquaredDistances {
+  /// 并行计算所有线段对之间的距离
+  pub(crate) fn new(line_segments: &[LineSegment], config: &DistanceConfig) -> Self {
+    let len = line_segments.len();
+    let rows: Vec<Vec<f64>> = (0..len).into_par_iter()
+      .map(|i| {
+        let (line_1_start, line_1_end) = line_segments[i].extract_start_end_points();
+        (i..len)
+          .map(|j| {
+            let (line_2_start, line_2_end) = line_segments[j].extract_start_end_points();
+            let distance = measure_distance_line_to_line(line_1_start, line_1_end, line_2_start, line_2_end, config);
+            if i == j { return distance.powi(2); }
+
+            distance.powi(2)
+              + measure_distance_line_to_line(line_2_start, line_2_end, line_1_start, line_1_end, config).powi(2)
+          })
+          .collect()
+      })
+      .collect();
+
+    Self { len, values: rows.concat() }
+  }
+
+  /// 获得第 i 条与第 j 条线段之间的值，要求 i <= j
+  fn get(&self, i: usize, j: usize) -> f64 {
+    // 第 i 行之前共有 i·n - i(i-1)/2 个值
+    self.values[i * (2 * self.len + 1 - i) / 2 + (j - i)]
+  }
+
+  /// 计算一组线段的 SSE，组内的序号需要升序排列，空组为 0
+  fn compute_sse(&self, group: &[usize]) -> f64 {
+    if group.is_empty() { return 0.0; }
+
+    let sum: f64 = group.par_iter()
+      .enumerate()
+      .map(|(a, i)| group[a..].iter().map(|j| self.get(*i, *j)).sum::<f64>())
+      .sum();
+
+    sum / (2.0 * group.len() as f64)
+  }
+}
@@ -0,0 +1,312 @@
+//! 长格式 CSV 轨迹读取，每行是一个轨迹点，例如 `traj_id,timestamp,x,y`
+use crate::{
+  models::{
+    point::Point,
+    trajectory::Trajectory
+  },
+  file_io::{
+    FileError,
+    FileErrorKind,
+    ErrorMode
+  }
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+
+/// CSV 中的一列，可以用表头中的列名或是从 0 开始的列序号指定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+  Name(String),
+  Index(usize),
+}
+
+impl CsvColumn {
+  /// 从命令行参数解析列，纯数字视为列序号，否则视为列名
+  pub fn parse(value: &str) -> Self {
+    match value.parse::<usize>() {
+      Ok(index) => CsvColumn::Index(index),
+      Err(_) => CsvColumn::Name(value.to_string())
+    }
+  }
+}
+
+/// CSV 读取配置
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+  id_column: CsvColumn,
+  x_column: CsvColumn,
+  y_column: CsvColumn,
+  z_column: Option<CsvColumn>,
+  time_column: Option<CsvColumn>,
+  delimiter: char,
+  has_header: bool,
+}
+
+impl Default for CsvOptions {
+  /// 默认读取带表头、以逗号分隔的 `traj_id`、`x`、`y` 列
+  fn default() -> Self {
+    Self::new(
+      CsvColumn::Name("traj_id".to_string()),
+      CsvColumn::Name("x".to_string()),
+      CsvColumn::Name("y".to_string())
+    )
+  }
+}
+
+impl CsvOptions {
+  /// 创建一个读取配置，默认带表头、以逗号分隔、不按时间排序
+  pub fn new(id_column: CsvColumn, x_column: CsvColumn, y_column: CsvColumn) -> Self {
+    Self {
+      id_column,
+      x_column,
+      y_column,
+      z_column: None,
+      time_column: None,
+      delimiter: ',',
+      has_header: true
+    }
+  }
+
+  /// 设置轨迹 id 列
+  pub fn set_id_column(&mut self, id_column: CsvColumn) {
+    self.id_column = id_column;
+  }
+
+  /// 设置 x 坐标列
+  pub fn set_x_column(&mut self, x_column: CsvColumn) {
+    self.x_column = x_column;
+  }
+
+  /// 设置 y 坐标列
+  pub fn set_y_column(&mut self, y_column: CsvColumn) {
+    self.y_column = y_column;
+  }
+
+  /// 设置 z 坐标列，设置后读取的轨迹点为三维点
+  pub fn set_z_column(&mut self, z_column: Option<CsvColumn>) {
+    self.z_column = z_column;
+  }
+
+  /// 设置时间列，时间会作为轨迹点的时间戳，同一轨迹的点会按照时间升序排列
+  pub fn set_time_column(&mut self, time_column: Option<CsvColumn>) {
+    self.time_column = time_column;
+  }
+
+  /// 设置分隔符
+  pub fn set_delimiter(&mut self, delimiter: char) {
+    self.delimiter = delimiter;
+  }
+
+  /// 设置第一行是否为表头
+  pub fn set_has_header(&mut self, has_header: bool) {
+    self.has_header = has_header;
+  }
+}
+
+/// 各个列在记录中的序号
+struct ColumnIndexs {
+  id: usize,
+  x: usize,
+  y: usize,
+  z: Option<usize>,
+  time: Option<usize>,
+}
+
+/// 读取 CSV 文件中的轨迹，遇到第一个错误就返回
+pub fn read_csv_trajectories(path: &str, options: &CsvOptions) -> Result<Vec<Trajectory>, FileError> {
+  let (trajectories, _) = read_csv_trajectories_with_mode(path, options, ErrorMode::FailFast)?;
+
+  Ok(trajectories)
+}
+
+/// 按照指定的处理方式读取 CSV 文件中的轨迹
+///
+/// 轨迹按照 id 第一次出现的顺序排列，
+/// 设置了时间列时同一轨迹的点按时间排序，否则保持文件中的顺序。
+pub fn read_csv_trajectories_with_mode(path: &str, options: &CsvOptions, mode: ErrorMode)
+  -> Result<(Vec<Trajectory>, Vec<FileError>), FileError>
+{
+  let content = fs::read_to_string(path).map_err(|e| FileError::new(path, FileErrorKind::Io(e)))?;
+  let (records, unterminated) = parse_records(path, &content, options.delimiter);
+  let mut warnings: Vec<FileError> = Vec::new();
+
+  if let Some(e) = unterminated {
+    if mode == ErrorMode::FailFast { return Err(e); }
+    warnings.push(e);
+  }
+
+  let mut records = records.into_iter();
+  let header = if options.has_header { records.next() } else { None };
+  let indexs = resolve_columns(path, options, header.as_ref())?;
+
+  // 轨迹 id 到轨迹序号的映射
+  let mut id_to_index: HashMap<usize, usize> = HashMap::new();
+  let mut grouped: Vec<(usize, Vec<Point>)> = Vec::new();
+
+  for (line_no, fields) in records {
+    match parse_record(path, line_no, &fields, &indexs) {
+      Ok((id, point)) => {
+        let index = *id_to_index.entry(id).or_insert_with(|| {
+          grouped.push((id, Vec::new()));
+          grouped.len() - 1
+        });
+        grouped[index].1.push(point);
+      },
+      Err(e) => {
+        if mode == ErrorMode::FailFast { return Err(e); }
+        warnings.push(e);
+      }
+    }
+  }
+
+  let trajectories = grouped.into_iter()
+    .map(|(id, mut points)| {
+      if indexs.time.is_some() {
+        points.sort_by(|lhs, rhs| lhs.get_t().partial_cmp(&rhs.get_t()).unwrap_or(Ordering::Equal));
+      }
+
+      let mut trajectory = Trajectory::new(id);
+      for point in points {
+        trajectory.add_point(point);
+      }
+      trajectory
+    })
+    .collect();
+
+  Ok((trajectories, warnings))
+}
+
+/// 将列配置解析为列序号
+fn resolve_columns(path: &str, options: &CsvOptions,
+  header: Option<&(usize, Vec<String>)>) -> Result<ColumnIndexs, FileError>
+{
+  let resolve = |column: &CsvColumn| -> Result<usize, FileError> {
+    match column {
+      CsvColumn::Index(index) => Ok(*index),
+      CsvColumn::Name(name) => {
+        let missing = || FileErrorKind::MissingColumn(name.clone());
+        let (line_no, names) = header.ok_or_else(|| FileError::new(path, missing()))?;
+
+        names.iter()
+          .position(|field| field.trim() == name)
+          .ok_or_else(|| FileError::at_line(path, *line_no, missing()))
+      }
+    }
+  };
+
+  let z = match &options.z_column {
+    Some(column) => Some(resolve(column)?),
+    None => None
+  };
+  let time = match &options.time_column {
+    Some(column) => Some(resolve(column)?),
+    None => None
+  };
+
+  Ok(ColumnIndexs {
+    id: resolve(&options.id_column)?,
+    x: resolve(&options.x_column)?,
+    y: resolve(&options.y_column)?,
+    z,
+    time
+  })
+}
+
+/// 解析一条记录，返回轨迹 id 以及轨迹点
+///
+/// 没有时间列时轨迹点没有时间戳
+fn parse_record(path: &str, line_no: usize, fields: &[String],
+  indexs: &ColumnIndexs) -> Result<(usize, Point), FileError>
+{
+  let field = |index: usize, kind: FileErrorKind| -> Result<&str, FileError> {
+    fields.get(index)
+      .map(|field| field.trim())
+      .ok_or_else(|| FileError::at_line(path, line_no, FileErrorKind::MissingField))
+      .and_then(|field| {
+        if field.is_empty() {
+          Err(FileError::at_token(path, line_no, index + 1, field, kind))
+        } else {
+          Ok(field)
+        }
+      })
+  };
+  let parse = |index: usize, kind: fn() -> FileErrorKind| -> Result<f64, FileError> {
+    let value = field(index, kind())?;
+    value.parse::<f64>().map_err(|_| FileError::at_token(path, line_no, index + 1, value, kind()))
+  };
+
+  let id_value = field(indexs.id, FileErrorKind::Id)?;
+  let id = id_value.parse::<usize>()
+    .map_err(|_| FileError::at_token(path, line_no, indexs.id + 1, id_value, FileErrorKind::Id))?;
+  let x = parse(indexs.x, || FileErrorKind::DimensionPoint)?;
+  let y = parse(indexs.y, || FileErrorKind::DimensionPoint)?;
+  let mut point = match indexs.z {
+    Some(index) => Point::new_3d(x, y, parse(index, || FileErrorKind::DimensionPoint)?),
+    None => Point::new(x, y)
+  };
+  if let Some(index) = indexs.time {
+    point.set_t(Some(parse(index, || FileErrorKind::Time)?));
+  }
+
+  Ok((id, point))
+}
+
+/// 将文件内容切分为记录，返回每条记录的起始行号与字段
+///
+/// 支持双引号包裹的字段，字段内可以包含分隔符、换行以及用 `""` 转义的引号。
+/// 空行会被忽略。如果最后一个引号没有闭合，会一并返回对应的错误。
+fn parse_records(path: &str, content: &str, delimiter: char) -> (Vec<(usize, Vec<String>)>, Option<FileError>) {
+  let mut records: Vec<(usize, Vec<String>)> = Vec::new();
+  let mut fields: Vec<String> = Vec::new();
+  let mut field = String::new();
+  let mut in_quotes = false;
+  let mut line_no = 1;
+  let mut record_line_no = 1;
+
+  let mut chars = content.chars().peekable();
+  while let Some(c) = chars.next() {
+    if in_quotes {
+      if c == '"' {
+        if chars.peek() == Some(&'"') {
+          field.push('"');
+          chars.next();
+        } else {
+          in_quotes = false;
+        }
+      } else {
+        if c == '\n' { line_no += 1; }
+        field.push(c);
+      }
+    } else if c == '"' && field.trim().is_empty() {
+      field.clear();
+      in_quotes = true;
+    } else if c == delimiter {
+      fields.push(mem::take(&mut field));
+    } else if c == '\n' {
+      fields.push(mem::take(&mut field));
+      push_record(&mut records, record_line_no, mem::take(&mut fields));
+      line_no += 1;
+      record_line_no = line_no;
+    } else if c != '\r' {
+      field.push(c);
+    }
+  }
+
+  if in_quotes {
+    return (records, Some(FileError::at_line(path, record_line_no, FileErrorKind::UnterminatedQuote)));
+  }
+
+  fields.push(field);
+  push_record(&mut records, record_line_no, fields);
+
+  (records, None)
+}
+
+/// 加入一条记录，忽略空行
+fn push_record(records: &mut Vec<(usize, Vec<String>)>, line_no: usize, fields: Vec<String>) {
+  if fields.len() == 1 && fields[0].trim().is_empty() { return; }
+
+  records.push((line_no, fields));
+}
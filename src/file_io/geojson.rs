@@ -0,0 +1,272 @@
+//! GeoJSON 轨迹读取与聚类结果输出
+use crate::{
+  models::{
+    point::{Point, MAX_DIMENSION},
+    trajectory::Trajectory,
+    line_segment::LineSegment,
+    cluster::Cluster
+  },
+  file_io::{
+    FileError,
+    FileErrorKind,
+    ErrorMode,
+    map_line_segment_clusters,
+    number_trajectory_parts,
+    write_atomic
+  }
+};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::io::{self, Write};
+
+/// 读取 GeoJSON 文件中的轨迹，遇到第一个错误就返回
+///
+/// `id_property` 是保存轨迹 id 的属性名，
+/// 属性中找不到时会使用 Feature 的 `id` 成员。
+pub fn read_geojson_trajectories(path: &str, id_property: &str) -> Result<Vec<Trajectory>, FileError> {
+  let (trajectories, _) = read_geojson_trajectories_with_mode(path, id_property, ErrorMode::FailFast)?;
+
+  Ok(trajectories)
+}
+
+/// 按照指定的处理方式读取 GeoJSON 文件中的轨迹
+///
+/// 坐标的第三个维度作为高程，得到三维点，更多的维度会被忽略；
+/// 同一条轨迹中各个坐标的维度必须相同。
+///
+/// 支持 FeatureCollection 或单个 Feature，
+/// 每个 `LineString` 成为一条轨迹；
+/// `MultiLineString` 的每一部分各自成为一条轨迹，它们共享同一个轨迹 id，
+/// 并按照出现的顺序由 `Trajectory::get_part` 区分。
+/// 其他类型的几何会被视为有误的记录。
+///
+/// 如果 Feature 有数值数组属性 `times`，它会按顺序作为各个点的时间戳，
+/// 对于 `MultiLineString`，`times` 是与各部分对应的数组的数组。
+pub fn read_geojson_trajectories_with_mode(path: &str, id_property: &str, mode: ErrorMode)
+  -> Result<(Vec<Trajectory>, Vec<FileError>), FileError>
+{
+  let content = fs::read_to_string(path).map_err(|e| FileError::new(path, FileErrorKind::Io(e)))?;
+  let root: Value = serde_json::from_str(&content).map_err(|e| FileError::new(path, FileErrorKind::Json(e)))?;
+  let invalid = |reason: String| FileError::new(path, FileErrorKind::GeoJson(reason));
+
+  let features: Vec<&Value> = match root.get("type").and_then(Value::as_str) {
+    Some("FeatureCollection") => {
+      root.get("features")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("FeatureCollection has no `features` array".to_string()))?
+        .iter()
+        .collect()
+    },
+    Some("Feature") => vec![&root],
+    _ => return Err(invalid("expected a FeatureCollection or a Feature".to_string()))
+  };
+
+  let mut trajectories: Vec<Trajectory> = Vec::new();
+  let mut warnings: Vec<FileError> = Vec::new();
+
+  for (index, feature) in features.into_iter().enumerate() {
+    match parse_feature(feature, id_property) {
+      Ok(mut parts) => trajectories.append(&mut parts),
+      Err(reason) => {
+        let e = invalid(format!("feature {}: {}", index, reason));
+        if mode == ErrorMode::FailFast { return Err(e); }
+        warnings.push(e);
+      }
+    }
+  }
+  number_trajectory_parts(&mut trajectories);
+
+  Ok((trajectories, warnings))
+}
+
+/// 解析一个 Feature，返回其中的所有轨迹
+fn parse_feature(feature: &Value, id_property: &str) -> Result<Vec<Trajectory>, String> {
+  let id_value = feature.get("properties")
+    .and_then(|properties| properties.get(id_property))
+    .or_else(|| feature.get("id"))
+    .ok_or_else(|| format!("property `{}` is missing", id_property))?;
+  let id = match id_value {
+    Value::Number(number) => number.as_u64().map(|id| id as usize),
+    Value::String(string) => string.trim().parse::<usize>().ok(),
+    _ => None
+  }.ok_or_else(|| format!("property `{}` isn't a usize", id_property))?;
+
+  let geometry = feature.get("geometry")
+    .filter(|geometry| !geometry.is_null())
+    .ok_or_else(|| "geometry is missing".to_string())?;
+  let coordinates = geometry.get("coordinates")
+    .ok_or_else(|| "geometry has no coordinates".to_string())?;
+
+  let geometry_type = geometry.get("type").and_then(Value::as_str);
+  let lines: Vec<&Value> = match geometry_type {
+    Some("LineString") => vec![coordinates],
+    Some("MultiLineString") => {
+      coordinates.as_array()
+        .ok_or_else(|| "coordinates of MultiLineString aren't an array".to_string())?
+        .iter()
+        .collect()
+    },
+    Some(other) => return Err(format!("geometry type `{}` isn't supported", other)),
+    None => return Err("geometry has no type".to_string())
+  };
+
+  let times = feature.get("properties").and_then(|properties| properties.get("times"));
+  let is_multi = geometry_type == Some("MultiLineString");
+
+  lines.into_iter()
+    .enumerate()
+    .map(|(part, line)| {
+      let positions = line.as_array()
+        .ok_or_else(|| "coordinates of LineString aren't an array".to_string())?;
+      let part_times = if is_multi { times.and_then(|times| times.get(part)) } else { times };
+      let part_times = parse_times(part_times, positions.len())?;
+
+      let mut trajectory = Trajectory::new(id);
+      for (index, position) in positions.iter().enumerate() {
+        let mut point = parse_position(position)?;
+        if index > 0 && point.get_dimension() != trajectory.get_point(0).map_or(0, Point::get_dimension) {
+          return Err("positions of LineString have different dimensions".to_string());
+        }
+        point.set_t(part_times.as_ref().map(|times| times[index]));
+        trajectory.add_point(point);
+      }
+
+      Ok(trajectory)
+    })
+    .collect()
+}
+
+/// 解析点的时间戳，数量必须与点的数量一致
+fn parse_times(times: Option<&Value>, len: usize) -> Result<Option<Vec<f64>>, String> {
+  let times = match times {
+    Some(times) => times,
+    None => return Ok(None)
+  };
+
+  let times: Option<Vec<f64>> = times.as_array()
+    .map(|times| times.iter().map(Value::as_f64).collect())
+    .unwrap_or(None);
+  match times {
+    Some(times) if times.len() == len => Ok(Some(times)),
+    Some(times) => Err(format!("expected {} times but found {}", len, times.len())),
+    None => Err("the time of point isn't a f64".to_string())
+  }
+}
+
+/// 解析一个坐标，最多使用前三个维度
+fn parse_position(position: &Value) -> Result<Point, String> {
+  let coords = position.as_array()
+    .filter(|coords| coords.len() >= 2)
+    .ok_or_else(|| "position needs at least two coordinates".to_string())?;
+
+  let coords: Vec<f64> = coords.iter()
+    .take(MAX_DIMENSION)
+    .map(Value::as_f64)
+    .collect::<Option<Vec<f64>>>()
+    .ok_or_else(|| "the coordinate of point isn't a f64".to_string())?;
+
+  Ok(Point::from_coords(&coords))
+}
+
+/// 将点转换为 GeoJSON 坐标
+fn position(point: &Point) -> Value {
+  json!(point.get_coords())
+}
+
+/// 将点集转换为 GeoJSON 几何，少于两个点时无法构成 LineString
+fn line_geometry(points: &[Point]) -> Value {
+  match points.len() {
+    0 => Value::Null,
+    1 => json!({ "type": "Point", "coordinates": position(&points[0]) }),
+    _ => json!({
+      "type": "LineString",
+      "coordinates": points.iter().map(position).collect::<Vec<Value>>()
+    })
+  }
+}
+
+/// 将聚类结果转换为 GeoJSON FeatureCollection
+///
+/// 每个簇的代表轨迹是一个 `kind` 为 `cluster` 的 Feature；
+/// 每条划分后的线段是一个 `kind` 为 `segment` 的 Feature，
+/// 其中 `trajectory_part` 是所属轨迹是输入中相同 id 的第几条记录，
+/// `segment_ordinal` 是线段在所属轨迹中的序号，`point_range` 是端点在原轨迹中的序号，
+/// `segment_cluster` 是 DBSCAN 给出的簇索引，
+/// `cluster_id` 是最终的簇 id，噪声或被舍弃的簇中的线段为 null。
+pub fn to_feature_collection(clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> Value
+{
+  let segment_cluster_to_id = map_line_segment_clusters(clusters);
+
+  let mut features: Vec<Value> = Vec::with_capacity(clusters.len() + line_segments.len());
+
+  for cluster in clusters {
+    features.push(json!({
+      "type": "Feature",
+      "geometry": line_geometry(cluster.get_points()),
+      "properties": {
+        "kind": "cluster",
+        "cluster_id": cluster.get_id(),
+        "segment_cluster": cluster.get_line_segment_cluster_id(),
+        "num_segments": cluster.get_num_of_line_segments(),
+        "num_trajectories": cluster.get_trajectory_ids().len(),
+        "trajectory_ids": cluster.get_trajectory_ids(),
+        "start_time": cluster.get_time_range().map(|(start, _)| start),
+        "end_time": cluster.get_time_range().map(|(_, end)| end)
+      }
+    }));
+  }
+
+  for (index, line_segment) in line_segments.iter().enumerate() {
+    let (start_point, end_point) = line_segment.extract_start_end_points();
+    let label = cluster_indexs.get(index).cloned().unwrap_or(-1);
+
+    let mut properties = Map::new();
+    properties.insert("kind".to_string(), json!("segment"));
+    properties.insert("segment_index".to_string(), json!(index));
+    properties.insert("trajectory_id".to_string(), json!(line_segment.get_trajectory_id()));
+    properties.insert("trajectory_part".to_string(), json!(line_segment.get_part()));
+    properties.insert("segment_ordinal".to_string(), json!(line_segment.get_segment_index()));
+    properties.insert("point_range".to_string(), json!(line_segment.get_point_range()));
+    properties.insert("start_time".to_string(), json!(line_segment.get_start_time()));
+    properties.insert("end_time".to_string(), json!(line_segment.get_end_time()));
+    if label >= 0 {
+      properties.insert("segment_cluster".to_string(), json!(label));
+      properties.insert("cluster_id".to_string(), json!(segment_cluster_to_id.get(&(label as usize))));
+    } else {
+      properties.insert("segment_cluster".to_string(), Value::Null);
+      properties.insert("cluster_id".to_string(), Value::Null);
+    }
+    properties.insert("noise".to_string(), json!(label < 0));
+
+    features.push(json!({
+      "type": "Feature",
+      "geometry": {
+        "type": "LineString",
+        "coordinates": [position(start_point), position(end_point)]
+      },
+      "properties": properties
+    }));
+  }
+
+  json!({
+    "type": "FeatureCollection",
+    "features": features
+  })
+}
+
+/// 将聚类结果以 GeoJSON 格式写入到指定的输出中
+pub fn write_geojson_to<W: Write + ?Sized>(writer: &mut W, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  serde_json::to_writer(writer, &to_feature_collection(clusters, line_segments, cluster_indexs))?;
+
+  Ok(())
+}
+
+/// 将聚类结果以 GeoJSON 格式写入到文件中
+pub fn write_geojson(out_path: &str, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  write_atomic(out_path, |writer| write_geojson_to(writer, clusters, line_segments, cluster_indexs))
+}
@@ -0,0 +1,166 @@
+//! 聚类结果的机器可读输出，记录每条线段的簇标签以及每个簇的成员轨迹
+use crate::{
+  models::{
+    point::Point,
+    line_segment::LineSegment,
+    cluster::Cluster
+  },
+  file_io::{
+    map_line_segment_clusters,
+    write_atomic
+  }
+};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+/// 噪声线段的标签
+static NOISE: i32 = -1;
+
+/// 将聚类结果转换为 JSON
+///
+/// `segments` 中的每一项对应一条划分后的线段，顺序与 `line_segments` 一致，
+/// `trajectory_part` 是所属轨迹是输入中相同 id 的第几条记录，
+/// `segment_ordinal` 是线段在所属轨迹中的序号，`point_range` 是端点在原轨迹中的序号，
+/// `segment_cluster` 是 DBSCAN 给出的簇索引，噪声为 -1，
+/// `cluster_id` 是最终的簇 id，噪声或被舍弃的簇中的线段为 null；
+/// `clusters` 中的每一项对应一个最终的簇及其成员轨迹。
+pub fn to_labels_json(clusters: &[Cluster], line_segments: &[LineSegment], cluster_indexs: &[i32]) -> Value {
+  let segment_cluster_to_id = map_line_segment_clusters(clusters);
+
+  let segments: Vec<Value> = line_segments.iter()
+    .enumerate()
+    .map(|(index, line_segment)| {
+      let (start_point, end_point) = line_segment.extract_start_end_points();
+      let label = cluster_indexs.get(index).cloned().unwrap_or(NOISE);
+      let cluster_id = if label >= 0 { segment_cluster_to_id.get(&(label as usize)).cloned() } else { None };
+
+      json!({
+        "segment_index": index,
+        "trajectory_id": line_segment.get_trajectory_id(),
+        "trajectory_part": line_segment.get_part(),
+        "segment_ordinal": line_segment.get_segment_index(),
+        "point_range": line_segment.get_point_range(),
+        "start": start_point.get_coords(),
+        "end": end_point.get_coords(),
+        "start_time": line_segment.get_start_time(),
+        "end_time": line_segment.get_end_time(),
+        "segment_cluster": label,
+        "cluster_id": cluster_id,
+        "noise": label < 0
+      })
+    })
+    .collect();
+
+  let clusters: Vec<Value> = clusters.iter()
+    .map(|cluster| {
+      json!({
+        "cluster_id": cluster.get_id(),
+        "segment_cluster": cluster.get_line_segment_cluster_id(),
+        "num_segments": cluster.get_num_of_line_segments(),
+        "trajectory_ids": cluster.get_trajectory_ids(),
+        "start_time": cluster.get_time_range().map(|(start, _)| start),
+        "end_time": cluster.get_time_range().map(|(_, end)| end),
+        "points": cluster.get_points().iter()
+          .map(|point| json!(point.get_coords()))
+          .collect::<Vec<Value>>()
+      })
+    })
+    .collect();
+
+  json!({
+    "segments": segments,
+    "clusters": clusters
+  })
+}
+
+/// 将线段标签与簇成员以 JSON 格式写入到指定的输出中
+pub fn write_labels_json_to<W: Write + ?Sized>(writer: &mut W, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  serde_json::to_writer(writer, &to_labels_json(clusters, line_segments, cluster_indexs))?;
+
+  Ok(())
+}
+
+/// 将线段标签与簇成员以 JSON 格式写入到文件中
+pub fn write_labels_json(out_path: &str, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  write_atomic(out_path, |writer| write_labels_json_to(writer, clusters, line_segments, cluster_indexs))
+}
+
+/// 将可选值转换为 CSV 字段，None 为空
+fn optional_field<T: ToString>(value: Option<T>) -> String {
+  value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// 将线段标签以 CSV 格式写入到指定的输出中，每行一条线段
+///
+/// 存在三维线段时会额外输出 `start_z`、`end_z` 列。
+/// `trajectory_part` 是所属轨迹是输入中相同 id 的第几条记录，`segment_ordinal` 是线段在所属轨迹中的序号，
+/// `start_point`、`end_point` 是端点在原轨迹中的序号。
+/// 噪声线段的 `segment_cluster` 为 -1，没有最终簇的线段 `cluster_id` 为空，
+/// 没有时间戳的线段 `start_time`、`end_time` 为空。
+pub fn write_labels_csv_to<W: Write + ?Sized>(writer: &mut W, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  let segment_cluster_to_id = map_line_segment_clusters(clusters);
+
+  let is_3d = line_segments.iter().any(|line_segment| {
+    let (start_point, end_point) = line_segment.extract_start_end_points();
+    start_point.get_dimension() > 2 || end_point.get_dimension() > 2
+  });
+  let coords_header = if is_3d { "start_x,start_y,start_z,end_x,end_y,end_z" } else { "start_x,start_y,end_x,end_y" };
+  let coords_field = |start_point: &Point, end_point: &Point| -> String {
+    let dimension = if is_3d { 3 } else { 2 };
+    let coords: Vec<String> = (0..dimension).map(|i| start_point.get_coord(i))
+      .chain((0..dimension).map(|i| end_point.get_coord(i)))
+      .map(|coord| coord.to_string())
+      .collect();
+    coords.join(",")
+  };
+
+  writeln!(writer, "segment_index,trajectory_id,trajectory_part,segment_ordinal,start_point,end_point,{},start_time,end_time,segment_cluster,cluster_id,noise", coords_header)?;
+  for (index, line_segment) in line_segments.iter().enumerate() {
+    let (start_point, end_point) = line_segment.extract_start_end_points();
+    let label = cluster_indexs.get(index).cloned().unwrap_or(NOISE);
+    let cluster_id = if label >= 0 { segment_cluster_to_id.get(&(label as usize)) } else { None };
+    let (start_point_index, end_point_index) = line_segment.get_point_range();
+
+    writeln!(writer, "{},{},{},{},{},{},{},{},{},{},{},{}",
+      index, line_segment.get_trajectory_id(), line_segment.get_part(), line_segment.get_segment_index(), start_point_index, end_point_index,
+      coords_field(start_point, end_point),
+      optional_field(line_segment.get_start_time()), optional_field(line_segment.get_end_time()),
+      label, optional_field(cluster_id), label < 0)?;
+  }
+
+  Ok(())
+}
+
+/// 将线段标签以 CSV 格式写入到文件中
+pub fn write_labels_csv(out_path: &str, clusters: &[Cluster], line_segments: &[LineSegment],
+  cluster_indexs: &[i32]) -> io::Result<()>
+{
+  write_atomic(out_path, |writer| write_labels_csv_to(writer, clusters, line_segments, cluster_indexs))
+}
+
+/// 将每个簇的成员轨迹以 CSV 格式写入到指定的输出中，每行是一个簇中的一条轨迹
+pub fn write_cluster_members_csv_to<W: Write + ?Sized>(writer: &mut W, clusters: &[Cluster]) -> io::Result<()> {
+  writeln!(writer, "cluster_id,segment_cluster,num_segments,start_time,end_time,trajectory_id")?;
+  for cluster in clusters {
+    let time_range = cluster.get_time_range();
+    for trajectory_id in cluster.get_trajectory_ids() {
+      writeln!(writer, "{},{},{},{},{},{}",
+        cluster.get_id(), cluster.get_line_segment_cluster_id(), cluster.get_num_of_line_segments(),
+        optional_field(time_range.map(|(start, _)| start)), optional_field(time_range.map(|(_, end)| end)),
+        trajectory_id)?;
+    }
+  }
+
+  Ok(())
+}
+
+/// 将每个簇的成员轨迹以 CSV 格式写入到文件中
+pub fn write_cluster_members_csv(out_path: &str, clusters: &[Cluster]) -> io::Result<()> {
+  write_atomic(out_path, |writer| write_cluster_members_csv_to(writer, clusters))
+}
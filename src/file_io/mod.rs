@@ -0,0 +1,453 @@
+pub mod csv;
+pub mod geojson;
+pub mod labels;
+
+use crate::{
+  models::{
+    point::{Point, MAX_DIMENSION},
+    trajectory::Trajectory,
+    cluster::Cluster
+  }
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 没有文件头时默认的点维度
+static DEFAULT_DIMENSION: usize = 2;
+/// 创建临时文件时最多尝试的次数
+static MAX_TEMP_ATTEMPTS: usize = 16;
+/// 本进程创建过的临时文件数量，保证同一进程中的临时文件名互不相同
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 读取轨迹时出错的原因
+#[derive(Debug)]
+pub enum FileErrorKind {
+  // 文件打开或读取有误
+  Io(io::Error),
+  // 轨迹 ID 读取有误
+  Id,
+  // 维度数量匹配有误
+  DimensionMismatch,
+  // 构建点有误
+  DimensionPoint,
+  // 文件头（维度行、轨迹数量行）有误
+  Header,
+  // 轨迹点数量缺失或读取有误
+  PointCount,
+  // 不支持文件头中声明的维度
+  UnsupportedDimension(usize),
+  // 轨迹点数量与记录中声明的不一致
+  PointCountMismatch { expected: usize, found: usize },
+  // 轨迹数量与文件头中声明的不一致
+  TrajectoryCountMismatch { expected: usize, found: usize },
+  // 表头中找不到指定的列
+  MissingColumn(String),
+  // 记录的字段数量不足
+  MissingField,
+  // 时间读取有误
+  Time,
+  // 引号没有闭合
+  UnterminatedQuote,
+  // JSON 格式有误
+  Json(serde_json::Error),
+  // GeoJSON 结构有误
+  GeoJson(String),
+}
+
+/// 读取轨迹时的错误，记录了出错的文件、行号、第几个字段以及原因
+#[derive(Debug)]
+pub struct FileError {
+  path: String,
+  line: Option<usize>,
+  column: Option<usize>,
+  token: Option<String>,
+  kind: FileErrorKind,
+}
+
+impl FileError {
+  /// 创建一个与具体行无关的错误
+  pub fn new(path: &str, kind: FileErrorKind) -> Self {
+    Self {
+      path: path.to_string(),
+      line: None,
+      column: None,
+      token: None,
+      kind
+    }
+  }
+
+  /// 创建一个定位到具体行的错误
+  pub fn at_line(path: &str, line: usize, kind: FileErrorKind) -> Self {
+    Self {
+      line: Some(line),
+      ..Self::new(path, kind)
+    }
+  }
+
+  /// 创建一个定位到具体字段的错误
+  ///
+  /// 行号与字段序号都从 1 开始计数
+  pub fn at_token(path: &str, line: usize, column: usize, token: &str, kind: FileErrorKind) -> Self {
+    Self {
+      line: Some(line),
+      column: Some(column),
+      token: Some(token.to_string()),
+      ..Self::new(path, kind)
+    }
+  }
+
+  /// 获得出错的文件路径
+  pub fn get_path(&self) -> &str {
+    &self.path
+  }
+
+  /// 获得出错的行号
+  pub fn get_line(&self) -> Option<usize> {
+    self.line
+  }
+
+  /// 获得出错的字段序号
+  pub fn get_column(&self) -> Option<usize> {
+    self.column
+  }
+
+  /// 获得出错的字段内容
+  pub fn get_token(&self) -> Option<&str> {
+    self.token.as_deref()
+  }
+
+  /// 获得出错的原因
+  pub fn get_kind(&self) -> &FileErrorKind {
+    &self.kind
+  }
+}
+
+impl fmt::Display for FileErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FileErrorKind::Io(e) => write!(f, "I/O error: {}", e),
+      FileErrorKind::Id => write!(f, "the id of trajectory isn't a usize"),
+      FileErrorKind::DimensionMismatch => write!(f, "the sum of coordinates isn't multiple of dimension"),
+      FileErrorKind::DimensionPoint => write!(f, "the coordinate of point isn't a f64"),
+      FileErrorKind::Header => write!(f, "the header of file isn't valid"),
+      FileErrorKind::PointCount => write!(f, "the point count of trajectory is missing or isn't a usize"),
+      FileErrorKind::UnsupportedDimension(dimension) => {
+        write!(f, "dimension {} isn't supported", dimension)
+      },
+      FileErrorKind::PointCountMismatch { expected, found } => {
+        write!(f, "expected {} points but found {}", expected, found)
+      },
+      FileErrorKind::TrajectoryCountMismatch { expected, found } => {
+        write!(f, "expected {} trajectories but found {}", expected, found)
+      },
+      FileErrorKind::MissingColumn(column) => write!(f, "column `{}` isn't in the header", column),
+      FileErrorKind::MissingField => write!(f, "the record has too few fields"),
+      FileErrorKind::Time => write!(f, "the time of point isn't a f64"),
+      FileErrorKind::UnterminatedQuote => write!(f, "the quoted field isn't closed"),
+      FileErrorKind::Json(e) => write!(f, "invalid JSON: {}", e),
+      FileErrorKind::GeoJson(reason) => write!(f, "invalid GeoJSON: {}", reason)
+    }
+  }
+}
+
+impl fmt::Display for FileError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.path)?;
+    if let Some(line) = self.line {
+      write!(f, ":{}", line)?;
+    }
+    if let Some(column) = self.column {
+      write!(f, ":{}", column)?;
+    }
+    if let Some(token) = &self.token {
+      write!(f, ": `{}`", token)?;
+    }
+    write!(f, ": {}", self.kind)
+  }
+}
+
+impl Error for FileError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match &self.kind {
+      FileErrorKind::Io(e) => Some(e),
+      FileErrorKind::Json(e) => Some(e),
+      _ => None
+    }
+  }
+}
+
+/// 遇到有误的轨迹记录时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorMode {
+  // 遇到第一个错误就返回
+  FailFast,
+  // 跳过有误的记录，并将错误作为警告收集起来
+  SkipInvalid,
+}
+
+/// 文件头信息
+///
+/// 原版 TraClus 的 .tra 文件以维度行与轨迹数量行开头，
+/// 每条记录为 `id npoints x y ...`；
+/// 而 deer_1995.tra 只有维度行，每条记录为 `id x y ...`。
+struct TraHeader {
+  dimension: usize,
+  trajectory_count: Option<usize>,
+}
+
+/// 读取文件的轨迹行，遇到第一个错误就返回
+///
+/// 同时支持以下三种格式：
+/// - 维度行 + 轨迹数量行，记录为 `id npoints x y ...`
+/// - 只有维度行，记录为 `id x y ...`
+/// - 没有文件头，记录为 `id x y ...`
+pub fn read_trajectory_lines(path: &str) -> Result<Vec<Trajectory>, FileError> {
+  let (trajectories, _) = read_trajectory_lines_with_mode(path, ErrorMode::FailFast)?;
+
+  Ok(trajectories)
+}
+
+/// 按照指定的处理方式读取文件的轨迹行
+///
+/// 返回读取到的轨迹以及被跳过的记录对应的警告，
+/// 在 `ErrorMode::FailFast` 下警告总是为空。
+/// 文件无法读取或文件头有误时总是直接返回错误。
+pub fn read_trajectory_lines_with_mode(path: &str, mode: ErrorMode)
+  -> Result<(Vec<Trajectory>, Vec<FileError>), FileError>
+{
+  let contents = File::open(path).map_err(|e| FileError::new(path, FileErrorKind::Io(e)))?;
+  let line_of_contents = BufReader::new(contents);
+
+  // 忽略空行，行尾多余的空白在切分时会被忽略
+  // 同时记录下每一行的行号，行号从 1 开始
+  let mut lines: Vec<(usize, String)> = Vec::new();
+  for (index, line) in line_of_contents.lines().enumerate() {
+    let line = line.map_err(|e| FileError::at_line(path, index + 1, FileErrorKind::Io(e)))?;
+    if !line.trim().is_empty() {
+      lines.push((index + 1, line));
+    }
+  }
+
+  let (header, header_len) = parse_header(path, &lines)?;
+  if !(2..=MAX_DIMENSION).contains(&header.dimension) {
+    return Err(FileError::at_line(path, lines[0].0, FileErrorKind::UnsupportedDimension(header.dimension)));
+  }
+
+  let has_point_count = header.trajectory_count.is_some();
+  let mut trajectorys: Vec<Trajectory> = Vec::new();
+  let mut warnings: Vec<FileError> = Vec::new();
+
+  // 根据文件构建轨迹
+  for (line_no, line) in lines.iter().skip(header_len) {
+    let trajectory_infos: Vec<&str> = line.split_whitespace().collect();
+
+    match parse_trajectory(path, *line_no, &trajectory_infos, header.dimension, has_point_count) {
+      Ok(trajectory) => trajectorys.push(trajectory),
+      Err(e) => {
+        if mode == ErrorMode::FailFast { return Err(e); }
+        warnings.push(e);
+      }
+    }
+  }
+
+  // 被跳过的记录也算在文件中出现过
+  if let Some(trajectory_count) = header.trajectory_count {
+    let found = lines.len() - header_len;
+    if trajectory_count != found {
+      let e = FileError::at_line(path, lines[1].0,
+        FileErrorKind::TrajectoryCountMismatch { expected: trajectory_count, found });
+      if mode == ErrorMode::FailFast { return Err(e); }
+      warnings.push(e);
+    }
+  }
+  number_trajectory_parts(&mut trajectorys);
+
+  Ok((trajectorys, warnings))
+}
+
+/// 为重复 id 的轨迹按照出现的顺序编号，使得轨迹 id 与编号一起能唯一确定一条轨迹
+fn number_trajectory_parts(trajectories: &mut [Trajectory]) {
+  let mut parts: HashMap<usize, usize> = HashMap::new();
+  for trajectory in trajectories.iter_mut() {
+    let part = parts.entry(trajectory.get_id()).or_insert(0);
+    trajectory.set_part(*part);
+    *part += 1;
+  }
+}
+
+/// 解析文件头，返回文件头信息以及文件头所占的行数
+fn parse_header(path: &str, lines: &[(usize, String)]) -> Result<(TraHeader, usize), FileError> {
+  let is_single_token = |index: usize| {
+    lines.get(index)
+      .map(|(_, line)| line.split_whitespace().count() == 1)
+      .unwrap_or(false)
+  };
+
+  // 第一行不是单独的维度，说明没有文件头
+  if !is_single_token(0) {
+    return Ok((TraHeader { dimension: DEFAULT_DIMENSION, trajectory_count: None }, 0));
+  }
+
+  let (line_no, line) = &lines[0];
+  let token = line.trim();
+  let dimension = match token.parse::<usize>() {
+    Ok(dimension) if dimension > 0 => dimension,
+    _ => return Err(FileError::at_token(path, *line_no, 1, token, FileErrorKind::Header))
+  };
+
+  // 第二行也是单独的数字，说明是轨迹数量行
+  if is_single_token(1) {
+    let (line_no, line) = &lines[1];
+    let token = line.trim();
+    let trajectory_count = token.parse::<usize>()
+      .map_err(|_| FileError::at_token(path, *line_no, 1, token, FileErrorKind::Header))?;
+    return Ok((TraHeader { dimension, trajectory_count: Some(trajectory_count) }, 2));
+  }
+
+  Ok((TraHeader { dimension, trajectory_count: None }, 1))
+}
+
+/// 解析一条轨迹记录
+fn parse_trajectory(path: &str, line_no: usize, trajectory_infos: &[&str],
+  dimension: usize, has_point_count: bool) -> Result<Trajectory, FileError>
+{
+  let token_error = |index: usize, kind: FileErrorKind| {
+    FileError::at_token(path, line_no, index + 1, trajectory_infos[index], kind)
+  };
+
+  let trajectory_id = trajectory_infos[0].parse::<usize>()
+    .map_err(|_| token_error(0, FileErrorKind::Id))?;
+  let mut trajectory = Trajectory::new(trajectory_id);
+
+  // 坐标开始的位置，注意要跳过 id 以及可能存在的点数量
+  let coords_start = if has_point_count { 2 } else { 1 };
+  if trajectory_infos.len() < coords_start {
+    return Err(FileError::at_line(path, line_no, FileErrorKind::PointCount));
+  }
+  let coords = &trajectory_infos[coords_start..];
+
+  // 点坐标的数量应该是维度的倍数
+  if !coords.len().is_multiple_of(dimension) {
+    return Err(FileError::at_line(path, line_no, FileErrorKind::DimensionMismatch));
+  }
+
+  if has_point_count {
+    let found = coords.len() / dimension;
+    let point_count = trajectory_infos[1].parse::<usize>()
+      .map_err(|_| token_error(1, FileErrorKind::PointCount))?;
+    if point_count != found {
+      return Err(token_error(1, FileErrorKind::PointCountMismatch { expected: point_count, found }));
+    }
+  }
+
+  // 构建轨迹点并加入到轨迹中
+  let parse_coord = |index: usize| {
+    trajectory_infos[index].parse::<f64>()
+      .map_err(|_| token_error(index, FileErrorKind::DimensionPoint))
+  };
+  let mut point_coords: Vec<f64> = Vec::with_capacity(dimension);
+  let mut i = coords_start;
+  while i < trajectory_infos.len() {
+    point_coords.clear();
+    for j in i..(i + dimension) {
+      point_coords.push(parse_coord(j)?);
+    }
+
+    trajectory.add_point(Point::from_coords(&point_coords));
+    i += dimension;
+  }
+
+  Ok(trajectory)
+}
+
+/// 获得 DBSCAN 簇索引到最终簇 id 的映射
+///
+/// 没有成为最终簇的 DBSCAN 簇不在映射中
+fn map_line_segment_clusters(clusters: &[Cluster]) -> HashMap<usize, usize> {
+  clusters.iter()
+    .map(|cluster| (cluster.get_line_segment_cluster_id(), cluster.get_id()))
+    .collect()
+}
+
+/// 获得与目标文件同目录的临时文件路径，同一进程中每次调用得到的路径都不同
+fn temp_path(out_path: &Path) -> PathBuf {
+  let file_name = out_path.file_name()
+    .map(|name| name.to_string_lossy().into_owned())
+    .unwrap_or_default();
+  let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+  out_path.with_file_name(format!(".{}.{}.{}.tmp", file_name, process::id(), counter))
+}
+
+/// 在目标文件同目录下创建一个新的临时文件
+///
+/// 临时文件必须是新建的，不会打开已经存在的文件或是跟随符号链接，
+/// 路径已被占用时换一个路径重试
+fn create_temp_file(out_path: &Path) -> io::Result<(PathBuf, File)> {
+  let mut attempts = 0;
+  loop {
+    let temp_path = temp_path(out_path);
+    match OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+      Ok(file) => return Ok((temp_path, file)),
+      Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && attempts + 1 < MAX_TEMP_ATTEMPTS => {
+        attempts += 1;
+      },
+      Err(e) => return Err(e)
+    }
+  }
+}
+
+/// 原子地写入文件
+///
+/// 内容先写入同目录下的临时文件，全部写完并落盘后再重命名为目标文件，
+/// 因此目标文件要么保持原样，要么是完整的新内容，不会残留旧内容。
+/// 写入失败时临时文件会被删除。
+/// 每次写入都使用各自新建的临时文件，因此同时写入同一个目标文件时，
+/// 目标文件总是其中某一次写入的完整内容。
+pub fn write_atomic<F>(out_path: &str, write: F) -> io::Result<()>
+  where F: FnOnce(&mut dyn Write) -> io::Result<()>
+{
+  let out_path = Path::new(out_path);
+  let (temp_path, file) = create_temp_file(out_path)?;
+
+  let mut writer = BufWriter::new(file);
+  let result = write(&mut writer)
+    .and_then(|_| writer.flush())
+    .and_then(|_| writer.get_ref().sync_all());
+  drop(writer);
+
+  match result.and_then(|_| fs::rename(&temp_path, out_path)) {
+    Ok(()) => Ok(()),
+    Err(e) => {
+      let _ = fs::remove_file(&temp_path);
+      Err(e)
+    }
+  }
+}
+
+/// 将簇写入到指定的输出中
+pub fn write_cluster_to<W: Write + ?Sized>(writer: &mut W, clusters: &[Cluster]) -> io::Result<()> {
+  for cluster in clusters {
+    let info_line = cluster.get_id().to_string() + " cluster\tpoint num: " + &cluster.get_len().to_string() + "\n";
+    writer.write_all(info_line.as_bytes())?;
+    for point in cluster.get_points() {
+      let coords: Vec<String> = point.get_coords().iter().map(|coord| coord.to_string()).collect();
+      let point_line = coords.join(" ") + "\t";
+      writer.write_all(point_line.as_bytes())?;
+    }
+    writer.write_all(b"\n")?;
+  }
+
+  Ok(())
+}
+
+/// 将簇写入到文件中
+pub fn write_cluster(out_path: &str, clusters: &[Cluster]) -> io::Result<()> {
+  write_atomic(out_path, |writer| write_cluster_to(writer, clusters))
+}
@@ -5,5 +5,12 @@ pub mod file_io;
 pub mod partition_tra;
 pub mod dbscan;
 pub mod cluster_gen;
+pub mod param_estimation;
+pub mod param_sweep;
+pub mod evaluation;
+pub mod clustering;
+pub mod distance_config;
+pub mod partition_config;
+pub mod projection;
 
 mod distance_util;
\ No newline at end of file
@@ -1,78 +1,457 @@
 extern crate traclus;
 
 use std::env;
+use std::io;
+use std::process;
+use rayon::ThreadPoolBuilder;
 use traclus::{
   models::{
     trajectory::Trajectory
   },
   file_io::{
-    read_trajectory_lines, 
+    read_trajectory_lines_with_mode,
     write_cluster,
-    FileError
+    write_cluster_to,
+    ErrorMode,
+    FileError,
+    csv::{
+      read_csv_trajectories_with_mode,
+      CsvColumn,
+      CsvOptions
+    },
+    geojson::{
+      read_geojson_trajectories_with_mode,
+      write_geojson
+    },
+    labels::{
+      write_labels_json,
+      write_labels_csv,
+      write_cluster_members_csv
+    }
   },
   partition_tra::{
     partition_trajectories,
     get_partition_line,
   },
-  dbscan::{
-    perform_dbscan
+  distance_config::{
+    DistanceConfig,
+    AngleMode,
+    CoordinateMode
+  },
+  partition_config::PartitionConfig,
+  projection::{
+    Projection,
+    ProjectionKind,
+    project_trajectories,
+    unproject_clusters,
+    unproject_line_segments
+  },
+  param_estimation::{
+    estimate_parameters,
+    MAX_CANDIDATE_EPS
+  },
+  param_sweep::{
+    sweep_parameters_with_gamma
+  },
+  evaluation::{
+    evaluate_clustering
   },
   cluster_gen::{
-    construct_line_segment_cluster,
-    construct_cluster
+    filter_clusters_by_time,
+    DEFAULT_GAMMA
+  },
+  clustering::{
+    TraClus
   }
 };
 
 fn main() {
   let args: Vec<String> = env::args().collect();
 
-  // args[1] input file
-  // args[2] output file
-  // args[3] eps
-  // args[4] minLns
-  if args.len() == 5 {
-    let eps: f64 = args[3].parse().expect("eps isn't a Double!");
-    let min_lns: usize = args[4].parse().expect("minLns isn't a Number!");
-
-    let trajectories: Vec<Trajectory>;
+  // 选项参数可以出现在任意位置，其余为位置参数
+  let mut mode = ErrorMode::FailFast;
+  let mut csv_options = CsvOptions::default();
+  let mut id_property = String::from("id");
+  let mut labels_path: Option<String> = None;
+  let mut members_path: Option<String> = None;
+  let mut time_window: Option<(f64, f64)> = None;
+  let mut distance_config = DistanceConfig::default();
+  let mut partition_config = PartitionConfig::default();
+  let mut directed = false;
+  let mut geographic = false;
+  let mut print_quality = false;
+  let mut projection_kind: Option<ProjectionKind> = None;
+  let mut num_threads: Option<usize> = None;
+  let mut min_trajectories: Option<usize> = None;
+  let mut gamma: Option<f64> = None;
+  let mut estimate_range: Option<(f64, f64, f64)> = None;
+  let mut sweep_eps: Option<Vec<f64>> = None;
+  let mut sweep_min_lns: Option<Vec<usize>> = None;
+  let mut positionals: Vec<&String> = Vec::new();
+  let mut iter = args.iter().skip(1);
+  while let Some(arg) = iter.next() {
+    match arg.as_str() {
+      "--skip-invalid" => { mode = ErrorMode::SkipInvalid; },
+      "--no-header" => { csv_options.set_has_header(false); },
+      "--directed" => { directed = true; },
+      "--geographic" => { geographic = true; },
+      "--quality" => { print_quality = true; },
+      "--id-col" | "--x-col" | "--y-col" | "--z-col" | "--time-col" | "--delimiter" | "--id-prop"
+        | "--labels" | "--members" | "--time-window" | "--weights" | "--project" | "--threads"
+        | "--min-trajectories" | "--estimate" | "--sweep-eps" | "--sweep-min-lns" | "--mdl-advantage"
+        | "--min-segment-length" | "--precision" | "--gamma" => {
+        let value = match iter.next() {
+          Some(value) => value,
+          None => {
+            eprintln!("error: {} needs a value", arg);
+            process::exit(1);
+          }
+        };
 
-    // 获得轨迹信息
-    match read_trajectory_lines(&args[1]) {
-      Ok(trajs) => { trajectories = trajs; },
-      Err(e) => {
-        match e {
-          FileError::DimensionMismatchError => {
-            eprintln!("The sum of infos isn't multiple of dimension!");
+        match arg.as_str() {
+          "--id-col" => csv_options.set_id_column(CsvColumn::parse(value)),
+          "--x-col" => csv_options.set_x_column(CsvColumn::parse(value)),
+          "--y-col" => csv_options.set_y_column(CsvColumn::parse(value)),
+          "--z-col" => csv_options.set_z_column(Some(CsvColumn::parse(value))),
+          "--time-col" => csv_options.set_time_column(Some(CsvColumn::parse(value))),
+          "--id-prop" => { id_property = value.clone(); },
+          "--labels" => { labels_path = Some(value.clone()); },
+          "--members" => { members_path = Some(value.clone()); },
+          "--time-window" => { time_window = Some(parse_time_window(value)); },
+          "--weights" => { distance_config = parse_weights(value); },
+          "--project" => { projection_kind = Some(parse_projection_kind(value)); },
+          "--threads" => { num_threads = Some(value.parse().expect("threads isn't a Number!")); },
+          "--min-trajectories" => { min_trajectories = Some(value.parse().expect("min-trajectories isn't a Number!")); },
+          "--estimate" => { estimate_range = Some(parse_estimate_range(value)); },
+          "--mdl-advantage" => {
+            partition_config.set_mdl_cost_advantage(value.parse().expect("mdl-advantage isn't a Number!"));
           },
-          FileError::DimensionPointError => {
-            eprintln!("The coordination of point isn't a f64!");
+          "--min-segment-length" => {
+            partition_config.set_min_line_segment_length(value.parse().expect("min-segment-length isn't a Double!"));
           },
-          FileError::IdError => {
-            eprintln!("The id of trajectory isn't a usize");
-          }
-          _ => {
-            eprintln!("Something wrong!");
-          }
+          "--precision" => { partition_config.set_precision(value.parse().expect("precision isn't a Double!")); },
+          "--gamma" => { gamma = Some(value.parse().expect("gamma isn't a Double!")); },
+          "--sweep-eps" => {
+            sweep_eps = Some(value.split(',').map(|eps| eps.trim().parse().expect("eps isn't a Double!")).collect());
+          },
+          "--sweep-min-lns" => {
+            sweep_min_lns = Some(value.split(',').map(|min_lns| min_lns.trim().parse().expect("minLns isn't a Number!")).collect());
+          },
+          _ => csv_options.set_delimiter(parse_delimiter(value))
         }
-        return;
+      },
+      _ => { positionals.push(arg); }
+    }
+  }
+
+  if directed {
+    distance_config.set_angle_mode(AngleMode::Directed);
+  }
+  if geographic {
+    if projection_kind.is_some() {
+      eprintln!("error: --geographic and --project can't be used together");
+      process::exit(1);
+    }
+    distance_config.set_coordinate_mode(CoordinateMode::Geographic);
+  }
+  if let Err(name) = distance_config.validate() {
+    eprintln!("error: the weight `{}` must be a finite non-negative number", name);
+    process::exit(1);
+  }
+  if let Err(name) = partition_config.validate() {
+    eprintln!("error: the partition parameter `{}` isn't valid", name);
+    process::exit(1);
+  }
+  if gamma.is_some_and(|gamma| !gamma.is_finite() || gamma < 0.0) {
+    eprintln!("error: the parameter `gamma` isn't valid");
+    process::exit(1);
+  }
+
+  // 估计参数时只需要输入文件
+  if estimate_range.is_some() && positionals.len() != 1 {
+    eprintln!("error: --estimate only takes inputFilePath, but {} positional arguments were given", positionals.len());
+    process::exit(1);
+  }
+  if let (Some((min_eps, max_eps, step)), 1) = (estimate_range, positionals.len()) {
+    let (trajectories, _) = load_trajectories(positionals[0], &csv_options, &id_property, mode, projection_kind);
+    let thick_trajectories = partition_trajectories(trajectories, &distance_config, &partition_config);
+    let line_segments = get_partition_line(&thick_trajectories, &distance_config, &partition_config);
+
+    match install(num_threads, || estimate_parameters(&line_segments, &distance_config, min_eps, max_eps, step)) {
+      Some(estimate) => {
+        println!("eps\tentropy");
+        for (eps, entropy) in estimate.get_entropies() {
+          println!("{}\t{}", eps, entropy);
+        }
+        println!("suggested eps: {}", estimate.get_eps());
+        println!("average neighbourhood size: {}", estimate.get_avg_neighbourhood_size());
+        println!("suggested minLns: {}", estimate.get_min_lns());
+      },
+      None => {
+        eprintln!("error: no line segment to estimate parameters, or the eps range is invalid \
+          (0 <= min <= max, step > 0 and at most {} candidates)", MAX_CANDIDATE_EPS);
+        process::exit(1);
       }
     }
+    return;
+  }
 
-    // 划分轨迹
-    let thick_trajectories = partition_trajectories(trajectories);
-    let line_segments = get_partition_line(&thick_trajectories);
+  // 参数扫描时只需要输入文件
+  if sweep_eps.is_some() != sweep_min_lns.is_some() {
+    eprintln!("error: --sweep-eps and --sweep-min-lns must be given together");
+    process::exit(1);
+  }
+  if sweep_eps.is_some() && positionals.len() != 1 {
+    eprintln!("error: --sweep-eps only takes inputFilePath, but {} positional arguments were given", positionals.len());
+    process::exit(1);
+  }
+  if let (Some(eps_values), Some(min_lns_values), 1) = (&sweep_eps, &sweep_min_lns, positionals.len()) {
+    let (trajectories, _) = load_trajectories(positionals[0], &csv_options, &id_property, mode, projection_kind);
+    let thick_trajectories = partition_trajectories(trajectories, &distance_config, &partition_config);
+    let line_segments = get_partition_line(&thick_trajectories, &distance_config, &partition_config);
+
+    let results = install(num_threads, || {
+      sweep_parameters_with_gamma(&line_segments, &distance_config, eps_values, min_lns_values, min_trajectories,
+        gamma.unwrap_or(DEFAULT_GAMMA))
+    });
+
+    println!("eps\tmin_lns\tclusters\tnoise_ratio\tq_measure");
+    for result in results {
+      println!("{}\t{}\t{}\t{}\t{}", result.get_eps(), result.get_min_lns(), result.get_num_of_clusters(),
+        result.get_noise_ratio(), result.get_q_measure());
+    }
+    return;
+  }
 
-    // 执行聚类
-    let (cluster_indexs, cluster_index) = perform_dbscan(eps, min_lns, &line_segments);
+  // positionals[0] input file
+  // positionals[1] output file
+  // positionals[2] eps
+  // positionals[3] minLns
+  if positionals.len() == 4 {
+    let eps: f64 = positionals[2].parse().expect("eps isn't a Double!");
+    let min_lns: usize = positionals[3].parse().expect("minLns isn't a Number!");
 
-    // 构建聚类
-    let line_segment_clusters = construct_line_segment_cluster(cluster_index, min_lns, cluster_indexs, line_segments);
-    let clusters = construct_cluster(line_segment_clusters);
+    // 获得轨迹信息，需要时将经纬度投影到平面坐标系中
+    let (trajectories, projection) = load_trajectories(positionals[0], &csv_options, &id_property, mode, projection_kind);
+
+    // 划分轨迹、执行聚类并构建聚类，没有指定线程数时使用全局线程池
+    let mut traclus = TraClus::new(eps, min_lns);
+    traclus.set_min_trajectories(min_trajectories)
+      .set_distance_config(distance_config)
+      .set_partition_config(partition_config)
+      .set_num_threads(num_threads);
+    if let Some(gamma) = gamma {
+      traclus.set_gamma(gamma);
+    }
+    let result = match traclus.run(trajectories) {
+      Ok(result) => result,
+      Err(e) => {
+        eprintln!("error: {}", e);
+        process::exit(1);
+      }
+    };
+    let line_segments = result.get_line_segments();
+    let cluster_indexs = result.get_cluster_indexs();
+
+    // 评估聚类质量，输出到标准错误以免与标准输出中的聚类信息混在一起
+    if print_quality {
+      let quality = evaluate_clustering(line_segments, cluster_indexs,
+        result.get_statistics().get_num_of_segment_clusters(), &distance_config);
+      eprintln!("total SSE: {}", quality.get_total_sse());
+      eprintln!("noise penalty: {}", quality.get_noise_penalty());
+      eprintln!("QMeasure: {}", quality.get_q_measure());
+    }
+
+    let mut clusters = result.get_clusters().clone();
+    if let Some((start, end)) = time_window {
+      clusters = filter_clusters_by_time(clusters, start, end);
+    }
+
+    // 将代表轨迹与线段还原为经纬度后再输出
+    let unprojected_segments = projection.as_ref()
+      .map(|projection| unproject_line_segments(line_segments, projection));
+    let line_segments = unprojected_segments.as_ref().unwrap_or(line_segments);
+    if let Some(projection) = &projection {
+      clusters = unproject_clusters(clusters, projection);
+    }
 
-    // 写聚类信息到文件中
-    write_cluster(&args[2], &clusters);
+    // 写聚类信息到文件中，`-` 表示写到标准输出
+    let output = positionals[1].as_str();
+    let result = if output == "-" {
+      write_cluster_to(&mut io::stdout().lock(), &clusters)
+    } else if is_geojson(output) {
+      write_geojson(output, &clusters, line_segments, cluster_indexs)
+    } else {
+      write_cluster(output, &clusters)
+    };
+    exit_on_write_error(output, result);
+
+    // 写线段标签与簇成员到文件中
+    if let Some(path) = &labels_path {
+      let result = if path.to_lowercase().ends_with(".csv") {
+        write_labels_csv(path, &clusters, line_segments, cluster_indexs)
+      } else {
+        write_labels_json(path, &clusters, line_segments, cluster_indexs)
+      };
+      exit_on_write_error(path, result);
+    }
+    if let Some(path) = &members_path {
+      exit_on_write_error(path, write_cluster_members_csv(path, &clusters));
+    }
   } else {
     println!("Please give me 4 input parameters(inputFilePath, outputFilePath, eps, minLns)!");
     println!("--e.g. cargo run deer_1995.tra testOut.txt 29 8");
+    println!("Use `-` as outputFilePath to write the clusters to stdout.");
+    println!("Options:");
+    println!("  --skip-invalid    skip invalid trajectory records and print them as warnings");
+    println!("  --id-col <col>    trajectory id column of .csv input, name or 0-based index (default traj_id)");
+    println!("  --x-col <col>     x column of .csv input (default x)");
+    println!("  --y-col <col>     y column of .csv input (default y)");
+    println!("  --z-col <col>     z column of .csv input, makes the points three-dimensional");
+    println!("  --time-col <col>  time column of .csv input, points are sorted by it");
+    println!("  --delimiter <c>   field delimiter of .csv input, `tab` for tabs (default ,)");
+    println!("  --no-header       the .csv input has no header line");
+    println!("  --id-prop <name>  trajectory id property of .geojson input (default id)");
+    println!("  --labels <path>   write the cluster label of every segment, as CSV for .csv or JSON otherwise");
+    println!("  --members <path>  write the member trajectories of every cluster as CSV");
+    println!("  --time-window <start,end>  only output clusters used within the time window");
+    println!("  --weights <perpendicular,parallel,angle>  weights of the segment distance (default 1,1,1)");
+    println!("  --directed        consider the direction of segments, opposite flows form separate clusters");
+    println!("  --geographic      coordinates are longitude,latitude in degrees, eps and lengths are in metres");
+    println!("  --project <utm|enu>  project longitude,latitude to UTM or a local east-north frame before clustering");
+    println!("  --mdl-advantage <n>  MDL cost advantage, a larger value gives fewer partition points (default 25)");
+    println!("  --min-segment-length <len>  shorter pieces are merged into the next segment, a shorter tail is dropped (default 50)");
+    println!("  --gamma <g>       minimum spacing of representative points, scale it with the minimum segment length (default 50/1.414)");
+    println!("  --precision <p>   precision of lengths and distances in the MDL cost, in coordinate units (default 1)");
+    println!("  --threads <n>     number of threads used by clustering, estimation and the parameter sweep (default: the global rayon pool)");
+    println!("  --min-trajectories <n>  clusters with fewer participating trajectories become noise (default minLns)");
+    println!("  --estimate <min,max,step>  with only inputFilePath, print the entropy of every eps and suggest eps and minLns");
+    println!("  --sweep-eps <e1,e2,..> --sweep-min-lns <m1,m2,..>  with only inputFilePath, print a summary of every combination");
+    println!("  --quality         print the total SSE, noise penalty and QMeasure of the clustering to stderr");
+    println!("Output ending with .geojson is written as a GeoJSON FeatureCollection.");
+  }
+}
+
+/// 根据扩展名选择读取方式，`.csv` 与 GeoJSON 以外的文件都视为 .tra 格式
+fn read_trajectories(path: &str, csv_options: &CsvOptions, id_property: &str, mode: ErrorMode)
+  -> Result<(Vec<Trajectory>, Vec<FileError>), FileError>
+{
+  if path.to_lowercase().ends_with(".csv") {
+    read_csv_trajectories_with_mode(path, csv_options, mode)
+  } else if is_geojson(path) {
+    read_geojson_trajectories_with_mode(path, id_property, mode)
+  } else {
+    read_trajectory_lines_with_mode(path, mode)
+  }
+}
+
+/// 读取轨迹并在需要时投影，读取失败时输出错误并退出
+fn load_trajectories(path: &str, csv_options: &CsvOptions, id_property: &str, mode: ErrorMode,
+  projection_kind: Option<ProjectionKind>) -> (Vec<Trajectory>, Option<Projection>)
+{
+  let trajectories = match read_trajectories(path, csv_options, id_property, mode) {
+    Ok((trajectories, warnings)) => {
+      for warning in warnings {
+        eprintln!("warning: skipped {}", warning);
+      }
+      trajectories
+    },
+    Err(e) => {
+      eprintln!("error: {}", e);
+      process::exit(1);
+    }
+  };
+
+  let projection = projection_kind.and_then(|kind| Projection::from_trajectories(kind, &trajectories));
+  match projection {
+    Some(projection) => (project_trajectories(trajectories, &projection), Some(projection)),
+    None => (trajectories, None)
+  }
+}
+
+/// 在有 `num_threads` 个线程的线程池中执行，没有指定时使用全局线程池，线程池无法创建时输出错误并退出
+fn install<T: Send>(num_threads: Option<usize>, op: impl FnOnce() -> T + Send) -> T {
+  match num_threads {
+    Some(num_threads) => match ThreadPoolBuilder::new().num_threads(num_threads).build() {
+      Ok(pool) => pool.install(op),
+      Err(e) => {
+        eprintln!("error: can't create the thread pool: {}", e);
+        process::exit(1);
+      }
+    },
+    None => op()
+  }
+}
+
+/// 写文件失败时输出错误并退出
+fn exit_on_write_error(path: &str, result: io::Result<()>) {
+  if let Err(e) = result {
+    eprintln!("error: can't write {}: {}", path, e);
+    process::exit(1);
+  }
+}
+
+/// 根据扩展名判断是否为 GeoJSON 文件
+fn is_geojson(path: &str) -> bool {
+  let path = path.to_lowercase();
+  path.ends_with(".geojson")
+}
+
+/// 解析 `start,end` 形式的时间段
+fn parse_time_window(value: &str) -> (f64, f64) {
+  let times: Vec<f64> = value.split(',')
+    .map(|time| time.trim().parse().expect("time of window isn't a Double!"))
+    .collect();
+  if times.len() != 2 {
+    eprintln!("error: --time-window needs `start,end`");
+    process::exit(1);
+  }
+
+  (times[0], times[1])
+}
+
+/// 解析 `perpendicular,parallel,angle` 形式的距离权重
+fn parse_weights(value: &str) -> DistanceConfig {
+  let weights: Vec<f64> = value.split(',')
+    .map(|weight| weight.trim().parse().expect("weight isn't a Double!"))
+    .collect();
+  if weights.len() != 3 {
+    eprintln!("error: --weights needs `perpendicular,parallel,angle`");
+    process::exit(1);
+  }
+
+  DistanceConfig::new(weights[0], weights[1], weights[2])
+}
+
+/// 解析 `min,max,step` 形式的 eps 搜索范围
+fn parse_estimate_range(value: &str) -> (f64, f64, f64) {
+  let values: Vec<f64> = value.split(',')
+    .map(|value| value.trim().parse().expect("eps of range isn't a Double!"))
+    .collect();
+  if values.len() != 3 {
+    eprintln!("error: --estimate needs `min,max,step`");
+    process::exit(1);
+  }
+
+  (values[0], values[1], values[2])
+}
+
+/// 解析投影方式
+fn parse_projection_kind(value: &str) -> ProjectionKind {
+  match value {
+    "utm" => ProjectionKind::Utm,
+    "enu" => ProjectionKind::LocalEnu,
+    _ => {
+      eprintln!("error: --project needs `utm` or `enu`");
+      process::exit(1);
+    }
+  }
+}
+
+/// 解析分隔符，`tab` 表示制表符
+fn parse_delimiter(value: &str) -> char {
+  match value {
+    "tab" | "\\t" => '\t',
+    _ => value.chars().next().unwrap_or(',')
   }
 }
@@ -3,16 +3,34 @@ use crate::{
     point::Point
   }
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cluster {
   id: usize,
+  line_segment_cluster_id: usize,
+  num_of_line_segments: usize,
+  trajectory_ids: Vec<usize>,
+  time_range: Option<(f64, f64)>,
   points: Vec<Point>
 }
 
 impl Cluster {
-  pub fn new(id: usize, points: Vec<Point>) -> Self {
+  /// 创建一个簇
+  ///
+  /// `line_segment_cluster_id` 是该簇在 DBSCAN 结果中的簇索引，
+  /// `trajectory_ids` 是参与该簇的轨迹 id
+  pub fn new(id: usize, line_segment_cluster_id: usize, num_of_line_segments: usize,
+    trajectory_ids: Vec<usize>, points: Vec<Point>) -> Self
+  {
     Self {
       id,
+      line_segment_cluster_id,
+      num_of_line_segments,
+      trajectory_ids,
+      time_range: None,
       points
     }
   }
@@ -22,6 +40,39 @@ impl Cluster {
     self.id
   }
 
+  /// 获得簇在 DBSCAN 结果中的簇索引
+  pub fn get_line_segment_cluster_id(&self) -> usize {
+    self.line_segment_cluster_id
+  }
+
+  /// 获得簇内线段的数量
+  pub fn get_num_of_line_segments(&self) -> usize {
+    self.num_of_line_segments
+  }
+
+  /// 获得参与该簇的轨迹 id，升序排列
+  pub fn get_trajectory_ids(&self) -> &Vec<usize> {
+    &self.trajectory_ids
+  }
+
+  /// 获得簇内线段的时间范围，即该通道被使用的时间段
+  pub fn get_time_range(&self) -> Option<(f64, f64)> {
+    self.time_range
+  }
+
+  /// 设置簇内线段的时间范围
+  pub fn set_time_range(&mut self, time_range: Option<(f64, f64)>) {
+    self.time_range = time_range;
+  }
+
+  /// 判断簇的时间范围是否与指定的时间段重叠，没有时间范围的簇总是不重叠
+  pub fn overlaps_time_window(&self, start: f64, end: f64) -> bool {
+    match self.time_range {
+      Some((range_start, range_end)) => range_start <= end && start <= range_end,
+      None => false
+    }
+  }
+
   /// 获得簇的长度
   pub fn get_len(&self) -> usize {
     self.points.len()
@@ -32,6 +83,11 @@ impl Cluster {
     &self.points
   }
 
+  /// 替换簇内所有点
+  pub fn set_points(&mut self, points: Vec<Point>) {
+    self.points = points;
+  }
+
   /// 簇内增加点
   pub fn add_point(&mut self, point: Point) {
     self.points.push(point);
@@ -3,50 +3,141 @@ use crate::{
     point::Point
   }
 };
-use uuid::Uuid;
 use std::hash::{Hash, Hasher};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-pub struct LineSegment<'a> {
-  uuid: Uuid,
-  start_point: &'a Point,
-  end_point: &'a Point,
-  trajectory_id: usize
+/// 线段的标识，由所属轨迹的 id、轨迹是该 id 的第几条记录以及线段在该轨迹中的序号组成
+///
+/// 相同的输入总是得到相同的标识，可以据此在输入文件中找到输出中的线段；
+/// 即使输入中有重复的轨迹 id，不同线段的标识也不会相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SegmentId {
+  trajectory_id: usize,
+  part: usize,
+  segment_index: usize
 }
 
-impl<'a> PartialEq for LineSegment<'a> {
+impl SegmentId {
+  /// 创建一个线段标识
+  pub fn new(trajectory_id: usize, part: usize, segment_index: usize) -> Self {
+    Self {
+      trajectory_id,
+      part,
+      segment_index
+    }
+  }
+
+  /// 获得所属轨迹的 id
+  pub fn get_trajectory_id(&self) -> usize {
+    self.trajectory_id
+  }
+
+  /// 获得所属轨迹是输入中相同 id 的第几条记录
+  pub fn get_part(&self) -> usize {
+    self.part
+  }
+
+  /// 获得线段在所属轨迹中的序号
+  pub fn get_segment_index(&self) -> usize {
+    self.segment_index
+  }
+}
+
+/// 划分后的一条线段
+///
+/// 线段拥有自己的端点，并记录它来自哪条轨迹的第几条划分线段，
+/// 以及端点在原轨迹中的序号，因此可以脱离划分后的轨迹单独保存与重新聚类。
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineSegment {
+  id: SegmentId,
+  start_point: Point,
+  end_point: Point,
+  point_range: (usize, usize)
+}
+
+impl PartialEq for LineSegment {
   fn eq(&self, other: &LineSegment) -> bool {
-    self.uuid == other.uuid
+    self.id == other.id
   }
 }
 
-impl<'a> Eq for LineSegment<'a> {}
+impl Eq for LineSegment {}
 
-impl<'a> Hash for LineSegment<'a> {
+impl Hash for LineSegment {
   fn hash<H: Hasher>(&self, state: &mut H) {
-    self.uuid.hash(state);
+    self.id.hash(state);
   }
 }
 
-impl<'a> LineSegment<'a> {
-  pub fn new(trajectory_id: usize, start_point: &'a Point, end_point: &'a Point) -> Self {
+impl LineSegment {
+  /// 创建一条线段
+  ///
+  /// `point_range` 是起点与终点在原轨迹中的序号
+  pub fn new(id: SegmentId, point_range: (usize, usize), start_point: Point, end_point: Point) -> Self {
     Self {
-      uuid: Uuid::new_v4(),
+      id,
       start_point,
       end_point,
-      trajectory_id
+      point_range
     }
   }
 
-  pub fn get_uuid(&self) -> &Uuid {
-    &self.uuid
+  /// 复制线段的所属信息并替换端点，例如把投影坐标还原为经纬度
+  pub fn with_points(&self, start_point: Point, end_point: Point) -> Self {
+    Self {
+      start_point,
+      end_point,
+      ..self.clone()
+    }
+  }
+
+  /// 获得线段的标识
+  pub fn get_id(&self) -> SegmentId {
+    self.id
   }
 
   /// 根据指定的线段获取起点与终点
   pub fn extract_start_end_points(&self) -> (&Point, &Point) {
-    (self.start_point, self.end_point)
+    (&self.start_point, &self.end_point)
   }
 
   pub fn get_trajectory_id(&self) -> usize {
-    self.trajectory_id
+    self.id.get_trajectory_id()
+  }
+
+  /// 获得所属轨迹是输入中相同 id 的第几条记录
+  pub fn get_part(&self) -> usize {
+    self.id.get_part()
+  }
+
+  /// 获得线段在所属轨迹中的序号
+  pub fn get_segment_index(&self) -> usize {
+    self.id.get_segment_index()
+  }
+
+  /// 获得起点与终点在原轨迹中的序号
+  pub fn get_point_range(&self) -> (usize, usize) {
+    self.point_range
+  }
+
+  /// 获得线段起点的时间戳
+  pub fn get_start_time(&self) -> Option<f64> {
+    self.start_point.get_t()
+  }
+
+  /// 获得线段终点的时间戳
+  pub fn get_end_time(&self) -> Option<f64> {
+    self.end_point.get_t()
+  }
+
+  /// 获得线段的持续时间，起点或终点没有时间戳时为 None
+  pub fn get_duration(&self) -> Option<f64> {
+    match (self.get_start_time(), self.get_end_time()) {
+      (Some(start), Some(end)) => Some(end - start),
+      _ => None
+    }
   }
 }
\ No newline at end of file
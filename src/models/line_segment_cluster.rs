@@ -5,16 +5,20 @@ use crate::{
   }
 };
 use std::collections::HashSet;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LineSegmentCluster {
   id: usize,
   avg_direction_vector: Point,
+  unit_direction_vector: Point,
   num_of_line_segments: usize,
-  cos_theta: f64,
-  sin_theta: f64,
   candidate_points: Vec<CandidatePoint>,
   points: Vec<Point>,
   trajectory_ids: HashSet<usize>,
+  time_range: Option<(f64, f64)>,
   enabled: bool
 }
 
@@ -23,20 +27,44 @@ impl LineSegmentCluster {
     Self {
       id,
       avg_direction_vector: Point::init(),
+      unit_direction_vector: Point::init(),
       num_of_line_segments: 0,
-      cos_theta: 0.0,
-      sin_theta: 0.0,
       candidate_points: Vec::new(),
       points: Vec::new(),
       trajectory_ids: HashSet::new(),
+      time_range: None,
       enabled: false
     }
   }
 
+  pub fn get_id(&self) -> usize {
+    self.id
+  }
+
   pub fn get_trajectory_length(&self) -> usize {
     self.trajectory_ids.len()
   }
 
+  /// 获得参与该簇的轨迹 id，升序排列
+  pub fn get_trajectory_ids(&self) -> Vec<usize> {
+    let mut trajectory_ids: Vec<usize> = self.trajectory_ids.iter().cloned().collect();
+    trajectory_ids.sort_unstable();
+    trajectory_ids
+  }
+
+  /// 获得簇内线段的时间范围，没有任何时间戳时为 None
+  pub fn get_time_range(&self) -> Option<(f64, f64)> {
+    self.time_range
+  }
+
+  /// 用一个时间戳扩展簇的时间范围
+  pub fn extend_time_range(&mut self, t: f64) {
+    self.time_range = match self.time_range {
+      Some((start, end)) => Some((start.min(t), end.max(t))),
+      None => Some((t, t))
+    };
+  }
+
   pub fn enable(&mut self) {
     self.enabled = true;
   }
@@ -49,41 +77,34 @@ impl LineSegmentCluster {
     self.enabled
   }
 
-  pub fn get_sin(&self) -> f64 {
-    self.sin_theta
-  }
-
-  pub fn get_cos(&self) -> f64 {
-    self.cos_theta
+  /// 获得平均方向上的单位向量
+  pub fn get_unit_direction_vector(&self) -> &Point {
+    &self.unit_direction_vector
   }
 
   pub fn add_point(&mut self, point: Point) {
     self.points.push(point);
   }
 
-  pub fn add_x(&mut self, value: f64) {
-    let origin = self.avg_direction_vector.get_x();
-    self.avg_direction_vector.set_x(value + origin);
-  }
-
-  pub fn add_y(&mut self, value: f64) {
-    let origin = self.avg_direction_vector.get_y();
-    self.avg_direction_vector.set_y(value + origin);
-  }
-
-  pub fn avg_direction_vector_x(&mut self) {
-    let val = self.avg_direction_vector.get_x();
-    self.avg_direction_vector.set_x(val / self.num_of_line_segments as f64);
+  /// 将线段的方向向量累加到方向向量之和中
+  pub fn add_direction_vector(&mut self, vector: &Point) {
+    for i in 0..vector.get_dimension() {
+      let origin = self.avg_direction_vector.get_coord(i);
+      self.avg_direction_vector.set_coord(i, vector.get_coord(i) + origin);
+    }
   }
 
-  pub fn avg_direction_vector_y(&mut self) {
-    let val = self.avg_direction_vector.get_y();
-    self.avg_direction_vector.set_y(val / self.num_of_line_segments as f64);
+  /// 将方向向量之和除以线段数量，得到平均方向向量
+  pub fn avg_direction_vector(&mut self) {
+    for i in 0..self.avg_direction_vector.get_dimension() {
+      let val = self.avg_direction_vector.get_coord(i);
+      self.avg_direction_vector.set_coord(i, val / self.num_of_line_segments as f64);
+    }
   }
 
-  pub fn set_theta(&mut self, cos_theta: f64, sin_thera: f64) {
-    self.sin_theta = sin_thera;
-    self.cos_theta = cos_theta;
+  /// 设置平均方向上的单位向量
+  pub fn set_unit_direction_vector(&mut self, unit_direction_vector: Point) {
+    self.unit_direction_vector = unit_direction_vector;
   }
 
   pub fn add_num_of_line_segments(&mut self) {
@@ -106,6 +127,10 @@ impl LineSegmentCluster {
     self.candidate_points.len()
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.candidate_points.is_empty()
+  }
+
   pub fn get_nth_candidate_point(&self, index: usize) -> &CandidatePoint {
     self.candidate_points.get(index).unwrap()
   }
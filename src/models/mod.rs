@@ -8,4 +8,4 @@ pub mod cluster;
 pub mod line_segment;
 pub mod line_segment_cluster;
 
-pub mod merge_indexs;
\ No newline at end of file
+pub mod segment_index;
@@ -1,43 +1,157 @@
-//! 二维点，包含 (x, y) 坐标值
+//! 多维点，包含二维 (x, y) 或三维 (x, y, z) 坐标值以及可选的时间戳
+//!
+//! 坐标固定存放在长度为 `MAX_DIMENSION` 的数组中，未使用的维度总是 0，
+//! 因此不同维度的点之间的计算等价于将低维点补 0。
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+/// 支持的最大维度
+pub const MAX_DIMENSION: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "RawPoint"))]
 pub struct Point {
-  x: f64,
-  y: f64
+  coords: [f64; MAX_DIMENSION],
+  dimension: usize,
+  t: Option<f64>
+}
+
+/// 反序列化得到的未经检查的点
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct RawPoint {
+  coords: [f64; MAX_DIMENSION],
+  dimension: usize,
+  t: Option<f64>
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawPoint> for Point {
+  type Error = String;
+
+  /// 检查维度是否受支持、未使用的维度是否为 0
+  fn try_from(raw: RawPoint) -> Result<Self, Self::Error> {
+    if !(2..=MAX_DIMENSION).contains(&raw.dimension) {
+      return Err(format!("dimension {} isn't supported", raw.dimension));
+    }
+    if raw.coords[raw.dimension..].iter().any(|coord| *coord != 0.0) {
+      return Err(format!("coordinates beyond dimension {} must be 0", raw.dimension));
+    }
+
+    Ok(Self {
+      coords: raw.coords,
+      dimension: raw.dimension,
+      t: raw.t
+    })
+  }
 }
 
 impl Point {
   /// 创建一个初始二维点
   pub fn init() -> Self {
+    Self::zero(2)
+  }
+
+  /// 创建一个指定维度的原点
+  pub fn zero(dimension: usize) -> Self {
+    assert!((2..=MAX_DIMENSION).contains(&dimension), "dimension {} isn't supported", dimension);
+
     Self {
-      x: 0.0,
-      y: 0.0
+      coords: [0.0; MAX_DIMENSION],
+      dimension,
+      t: None
     }
   }
 
   /// 创建一个二维点
   pub fn new(x: f64, y: f64) -> Self {
-    Self {
-      x,
-      y
-    }
+    Self::from_coords(&[x, y])
+  }
+
+  /// 创建一个三维点
+  pub fn new_3d(x: f64, y: f64, z: f64) -> Self {
+    Self::from_coords(&[x, y, z])
+  }
+
+  /// 根据坐标创建点，坐标的数量即为点的维度
+  pub fn from_coords(coords: &[f64]) -> Self {
+    let mut point = Self::zero(coords.len());
+    point.coords[..coords.len()].copy_from_slice(coords);
+
+    point
+  }
+
+  /// 创建一个带时间戳的二维点
+  pub fn with_time(x: f64, y: f64, t: f64) -> Self {
+    let mut point = Self::new(x, y);
+    point.t = Some(t);
+
+    point
+  }
+
+  /// 获得点的维度
+  pub fn get_dimension(&self) -> usize {
+    self.dimension
+  }
+
+  /// 获得点的所有坐标
+  pub fn get_coords(&self) -> &[f64] {
+    &self.coords[..self.dimension]
+  }
+
+  /// 获得点指定维度的坐标，超出点的维度时为 0
+  pub fn get_coord(&self, index: usize) -> f64 {
+    self.coords.get(index).cloned().unwrap_or(0.0)
   }
 
   /// 获得点的 x 坐标
   pub fn get_x(&self) -> f64 {
-    self.x
+    self.coords[0]
   }
 
   /// 获得点的 y 坐标
   pub fn get_y(&self) -> f64 {
-    self.y
+    self.coords[1]
+  }
+
+  /// 获得点的 z 坐标，二维点总是 0
+  pub fn get_z(&self) -> f64 {
+    self.coords[2]
+  }
+
+  /// 获得点的时间戳
+  pub fn get_t(&self) -> Option<f64> {
+    self.t
+  }
+
+  /// 设置点指定维度的坐标，必要时会提升点的维度
+  pub fn set_coord(&mut self, index: usize, value: f64) {
+    self.coords[index] = value;
+    if index >= self.dimension {
+      self.dimension = index + 1;
+    }
   }
 
   /// 设置点的 x 坐标
   pub fn set_x(&mut self, x: f64) {
-    self.x = x;
+    self.coords[0] = x;
   }
 
   /// 设置点的 y 坐标
   pub fn set_y(&mut self, y: f64) {
-    self.y = y;
+    self.coords[1] = y;
   }
-}
\ No newline at end of file
+
+  /// 设置点的 z 坐标，二维点会成为三维点
+  pub fn set_z(&mut self, z: f64) {
+    self.set_coord(2, z);
+  }
+
+  /// 设置点的时间戳
+  pub fn set_t(&mut self, t: Option<f64>) {
+    self.t = t;
+  }
+}
@@ -0,0 +1,197 @@
+//! 线段的空间索引，帮助 DBSCAN 快速找到可能在 eps 范围内的线段
+use crate::{
+  models::{
+    line_segment::LineSegment
+  },
+  distance_config::CoordinateMode,
+  distance_util::EARTH_RADIUS
+};
+use std::collections::HashMap;
+
+/// 一条线段最多占据的网格数量，超过的线段会放入 `oversized` 中，每次查询都会返回
+static MAX_CELLS_PER_SEGMENT: i64 = 64;
+
+/// 线段在 xy 平面上的包围盒，依次为 min_x、min_y、max_x、max_y
+type BoundingBox = [f64; 4];
+
+/// 基于均匀网格的线段索引
+///
+/// 每条线段按照包围盒放入所有覆盖的网格中，
+/// 查询时只返回包围盒之间的距离不超过 `radius` 的线段。
+/// 包围盒只考虑 x、y 两个维度，它们的距离不会超过三维线段之间的距离，因此不会漏掉线段。
+///
+/// 经纬度坐标下搜索半径的单位为米，包围盒建立在 (经度 · cos(φmax), 纬度) 平面上，
+/// 其中 φmax 是所有端点纬度绝对值的最大值，半径相应地换算为度。
+/// 局部切平面上的距离不小于该平面上的距离乘以地球半径，因此同样不会漏掉线段。
+/// 这要求所有经度落在小于 180° 的范围内，否则无法在平面上展开，退化为暴力查询。
+///
+/// 没有搜索半径时退化为暴力查询，总是返回所有线段。
+pub struct SegmentIndex {
+  radius: Option<f64>,
+  cell_size: f64,
+  bounding_boxes: Vec<BoundingBox>,
+  cells: HashMap<(i64, i64), Vec<usize>>,
+  oversized: Vec<usize>,
+}
+
+impl SegmentIndex {
+  /// 为所有线段建立索引，`radius` 是两条线段之间欧氏距离的查询上界，
+  /// 经纬度坐标下为局部切平面上的距离，单位为米
+  pub fn new(line_segments: &[LineSegment], radius: Option<f64>, coordinate_mode: CoordinateMode) -> Self {
+    let (bounding_boxes, radius) = match coordinate_mode {
+      CoordinateMode::Planar => (compute_bounding_boxes(line_segments, |x, y| (x, y)), radius),
+      CoordinateMode::Geographic => match compute_geographic_frame(line_segments) {
+        Some((base_lon, scale)) => {
+          let to_plane = |lon: f64, lat: f64| ((lon - base_lon).rem_euclid(360.0) * scale, lat);
+          (compute_bounding_boxes(line_segments, to_plane), radius.map(|radius| (radius / EARTH_RADIUS).to_degrees()))
+        },
+        None => (compute_bounding_boxes(line_segments, |x, y| (x, y)), None)
+      }
+    };
+
+    // 网格不小于搜索半径与线段的平均跨度，避免线段占据过多网格
+    let mean_extent = if bounding_boxes.is_empty() { 0.0 } else {
+      bounding_boxes.iter()
+        .map(|bounding_box| (bounding_box[2] - bounding_box[0]).max(bounding_box[3] - bounding_box[1]))
+        .sum::<f64>() / bounding_boxes.len() as f64
+    };
+    let cell_size = radius.unwrap_or(0.0).max(mean_extent);
+    let cell_size = if cell_size.is_finite() && cell_size > 0.0 { cell_size } else { 1.0 };
+
+    let mut index = Self {
+      radius,
+      cell_size,
+      bounding_boxes,
+      cells: HashMap::new(),
+      oversized: Vec::new(),
+    };
+    if index.radius.is_none() { return index; }
+
+    for i in 0..index.bounding_boxes.len() {
+      let (min_cell, max_cell) = index.cell_range(&index.bounding_boxes[i], 0.0);
+
+      if count_cells(min_cell, max_cell) > MAX_CELLS_PER_SEGMENT {
+        index.oversized.push(i);
+        continue;
+      }
+      for cell_x in min_cell.0..=max_cell.0 {
+        for cell_y in min_cell.1..=max_cell.1 {
+          index.cells.entry((cell_x, cell_y)).or_default().push(i);
+        }
+      }
+    }
+
+    index
+  }
+
+  /// 获得可能与第 `index` 条线段的距离不超过搜索半径的所有线段序号，升序排列且包含自身
+  pub fn query(&self, index: usize) -> Vec<usize> {
+    let radius = match self.radius {
+      Some(radius) => radius,
+      None => return (0..self.bounding_boxes.len()).collect()
+    };
+
+    let bounding_box = &self.bounding_boxes[index];
+    let (min_cell, max_cell) = self.cell_range(bounding_box, radius);
+
+    let mut candidates: Vec<usize> = self.oversized.clone();
+    if count_cells(min_cell, max_cell) as usize > self.cells.len() {
+      // 查询范围比已有的网格还多时直接遍历已有网格
+      for ((cell_x, cell_y), segments) in self.cells.iter() {
+        if (min_cell.0..=max_cell.0).contains(cell_x) && (min_cell.1..=max_cell.1).contains(cell_y) {
+          candidates.extend(segments);
+        }
+      }
+    } else {
+      for cell_x in min_cell.0..=max_cell.0 {
+        for cell_y in min_cell.1..=max_cell.1 {
+          if let Some(segments) = self.cells.get(&(cell_x, cell_y)) {
+            candidates.extend(segments);
+          }
+        }
+      }
+    }
+
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates.retain(|candidate| {
+      measure_box_distance(bounding_box, &self.bounding_boxes[*candidate]) <= radius
+    });
+
+    candidates
+  }
+
+  /// 获得包围盒向外扩展 `margin` 后覆盖的网格范围
+  fn cell_range(&self, bounding_box: &BoundingBox, margin: f64) -> ((i64, i64), (i64, i64)) {
+    let cell = |value: f64| (value / self.cell_size).floor() as i64;
+
+    (
+      (cell(bounding_box[0] - margin), cell(bounding_box[1] - margin)),
+      (cell(bounding_box[2] + margin), cell(bounding_box[3] + margin))
+    )
+  }
+}
+
+/// 计算所有线段在 `to_plane` 给出的平面上的包围盒
+fn compute_bounding_boxes<F>(line_segments: &[LineSegment], to_plane: F) -> Vec<BoundingBox>
+  where F: Fn(f64, f64) -> (f64, f64)
+{
+  line_segments.iter()
+    .map(|line_segment| {
+      let (start_point, end_point) = line_segment.extract_start_end_points();
+      let (start_x, start_y) = to_plane(start_point.get_x(), start_point.get_y());
+      let (end_x, end_y) = to_plane(end_point.get_x(), end_point.get_y());
+      [start_x.min(end_x), start_y.min(end_y), start_x.max(end_x), start_y.max(end_y)]
+    })
+    .collect()
+}
+
+/// 计算经纬度坐标展开到平面时的起始经度与经度的缩放比例 cos(φmax)
+///
+/// 起始经度取在所有端点经度之间最大的空隙之后，使得展开后的经度之差就是实际的经度之差；
+/// 最大的空隙不超过 180° 时，所有经度无法放在半个圆周内，返回 None
+fn compute_geographic_frame(line_segments: &[LineSegment]) -> Option<(f64, f64)> {
+  let mut longitudes: Vec<f64> = Vec::with_capacity(line_segments.len() * 2);
+  let mut max_latitude: f64 = 0.0;
+  for line_segment in line_segments {
+    let (start_point, end_point) = line_segment.extract_start_end_points();
+    for point in &[start_point, end_point] {
+      longitudes.push(point.get_x().rem_euclid(360.0));
+      max_latitude = max_latitude.max(point.get_y().abs());
+    }
+  }
+  if longitudes.iter().any(|lon| !lon.is_finite()) || !max_latitude.is_finite() { return None; }
+  longitudes.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+
+  let (first, last) = match (longitudes.first(), longitudes.last()) {
+    (Some(first), Some(last)) => (*first, *last),
+    _ => return Some((0.0, 1.0))
+  };
+  // 首尾之间绕过 0° 的空隙
+  let (mut max_gap, mut base_lon) = (first + 360.0 - last, first);
+  for window in longitudes.windows(2) {
+    if window[1] - window[0] > max_gap {
+      max_gap = window[1] - window[0];
+      base_lon = window[1];
+    }
+  }
+  if max_gap <= 180.0 { return None; }
+
+  Some((base_lon, max_latitude.min(90.0).to_radians().cos()))
+}
+
+/// 计算网格范围内的网格数量
+fn count_cells(min_cell: (i64, i64), max_cell: (i64, i64)) -> i64 {
+  let width = max_cell.0.saturating_sub(min_cell.0).saturating_add(1);
+  let height = max_cell.1.saturating_sub(min_cell.1).saturating_add(1);
+
+  width.saturating_mul(height)
+}
+
+/// 计算两个包围盒之间的最短距离，重叠时为 0
+fn measure_box_distance(lhs: &BoundingBox, rhs: &BoundingBox) -> f64 {
+  let diff_x = (lhs[0] - rhs[2]).max(rhs[0] - lhs[2]).max(0.0);
+  let diff_y = (lhs[1] - rhs[3]).max(rhs[1] - lhs[3]).max(0.0);
+
+  (diff_x * diff_x + diff_y * diff_y).sqrt()
+}
@@ -4,18 +4,31 @@ use crate::{
     point::Point
   }
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ThickTrajectory {
   id: usize,
-  partition_points: Vec<Point>
+  part: usize,
+  partition_points: Vec<Point>,
+  point_indexs: Vec<usize>
 }
 
 impl ThickTrajectory {
   /// 创建一条划分后轨迹
-  pub fn new(id: usize, partition_points: Vec<Point>) -> Self {
+  ///
+  /// `part` 与原轨迹的 `Trajectory::get_part` 相同，
+  /// `point_indexs` 是每个划分点在原轨迹中的序号，与 `partition_points` 一一对应
+  pub fn new(id: usize, part: usize, partition_points: Vec<Point>, point_indexs: Vec<usize>) -> Self {
+    assert_eq!(partition_points.len(), point_indexs.len(), "every partition point needs its index");
+
     Self {
       id,
-      partition_points
+      part,
+      partition_points,
+      point_indexs
     }
   }
 
@@ -29,8 +42,18 @@ impl ThickTrajectory {
     self.id
   }
 
+  /// 获得原轨迹是输入中相同 id 的第几条记录
+  pub fn get_part(&self) -> usize {
+    self.part
+  }
+
   /// 获得指定索引的划分点
   pub fn get_partition_point(&self, index: usize) -> Option<&Point> {
     self.partition_points.get(index)
   }
+
+  /// 获得指定索引的划分点在原轨迹中的序号
+  pub fn get_point_index(&self, index: usize) -> Option<usize> {
+    self.point_indexs.get(index).cloned()
+  }
 }
\ No newline at end of file
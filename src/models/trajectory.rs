@@ -4,9 +4,14 @@ use crate::{
     point::Point
   }
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Trajectory {
   id: usize,
+  part: usize,
   points: Vec<Point>
 }
 
@@ -15,6 +20,7 @@ impl Trajectory {
   pub fn new(id: usize) -> Self {
     Self {
       id,
+      part: 0,
       points: Vec::new()
     }
   }
@@ -24,6 +30,19 @@ impl Trajectory {
     self.id
   }
 
+  /// 获得轨迹是输入中相同 id 的第几条记录，从 0 开始
+  ///
+  /// 例如 `MultiLineString` 的各个部分或 .tra 文件中重复 id 的记录，
+  /// 轨迹 id 与该序号一起才能唯一确定一条轨迹
+  pub fn get_part(&self) -> usize {
+    self.part
+  }
+
+  /// 设置轨迹是输入中相同 id 的第几条记录
+  pub fn set_part(&mut self, part: usize) {
+    self.part = part;
+  }
+
   /// 给轨迹添加轨迹点
   pub fn add_point(&mut self, point: Point) {
     self.points.push(point);
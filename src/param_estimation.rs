@@ -0,0 +1,122 @@
+//! 参数估计，按照论文 4.4 节的启发式方法选择 eps 与 MinLns
+//!
+//! 对于给定的 eps，记 p(L) = |Nε(L)| / Σ|Nε(L')|，邻域大小分布的熵为 H = -Σ p(L) log2 p(L)。
+//! 熵最小的 eps 使得邻域大小的分布最不均匀，即簇与噪声的区分最明显；
+//! MinLns 取该 eps 下平均邻域大小加 1 到 3。
+use crate::{
+  models::{
+    line_segment::LineSegment
+  },
+  dbscan::find_neighbours,
+  distance_config::DistanceConfig
+};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+
+/// 建议的 MinLns 比平均邻域大小多出的数量，论文中为 1 到 3
+static MIN_LNS_MARGIN: f64 = 2.0;
+/// 候选 eps 的最大数量，步长过小时拒绝搜索
+pub static MAX_CANDIDATE_EPS: usize = 100_000;
+
+/// 参数估计的结果
+#[derive(Debug, Clone)]
+pub struct ParameterEstimate {
+  eps: f64,
+  min_lns: usize,
+  avg_neighbourhood_size: f64,
+  entropies: Vec<(f64, f64)>
+}
+
+impl ParameterEstimate {
+  /// 获得熵最小的 eps
+  pub fn get_eps(&self) -> f64 {
+    self.eps
+  }
+
+  /// 获得建议的 MinLns
+  pub fn get_min_lns(&self) -> usize {
+    self.min_lns
+  }
+
+  /// 获得建议的 eps 下的平均邻域大小，邻域包含线段自身
+  pub fn get_avg_neighbourhood_size(&self) -> f64 {
+    self.avg_neighbourhood_size
+  }
+
+  /// 获得每个候选 eps 以及对应的熵，按照 eps 升序排列
+  pub fn get_entropies(&self) -> &Vec<(f64, f64)> {
+    &self.entropies
+  }
+}
+
+/// 在 [min_eps, max_eps] 中以 step 为步长搜索使熵最小的 eps，并给出建议的 MinLns
+///
+/// 每条线段到其他线段的距离只计算一次，因此搜索的代价与候选 eps 的数量几乎无关。
+/// 没有线段、搜索范围无效（包括 `min_eps` 为负数）或者候选 eps 多于 `MAX_CANDIDATE_EPS` 个时返回 None。
+pub fn estimate_parameters(line_segments: &[LineSegment], config: &DistanceConfig,
+  min_eps: f64, max_eps: f64, step: f64) -> Option<ParameterEstimate>
+{
+  let is_valid_range = min_eps.is_finite() && max_eps.is_finite() && step.is_finite()
+    && min_eps >= 0.0 && step > 0.0 && min_eps <= max_eps;
+  if line_segments.is_empty() || !is_valid_range { return None; }
+  if (max_eps - min_eps) / step >= MAX_CANDIDATE_EPS as f64 { return None; }
+
+  let candidate_eps: Vec<f64> = (0..)
+    .map(|i| min_eps + step * i as f64)
+    // 容许浮点误差，使得 max_eps 本身也会被搜索
+    .take_while(|eps| *eps <= max_eps + step * 1e-9)
+    .map(|eps| eps.min(max_eps))
+    .collect();
+
+  // 每条线段到 max_eps 范围内所有线段的距离，升序排列
+  let distances: Vec<Vec<f64>> = find_neighbours(max_eps, line_segments, config)
+    .into_par_iter()
+    .map(|neighbour| {
+      let mut distances: Vec<f64> = neighbour.into_iter().map(|(_, distance)| distance).collect();
+      distances.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal));
+      distances
+    })
+    .collect();
+
+  let neighbourhood_sizes = |eps: f64| -> Vec<usize> {
+    distances.iter()
+      .map(|distances| distances.partition_point(|distance| *distance <= eps))
+      .collect()
+  };
+
+  let entropies: Vec<(f64, f64)> = candidate_eps.iter()
+    .map(|eps| (*eps, compute_entropy(&neighbourhood_sizes(*eps))))
+    .collect();
+
+  // 熵相同时取较小的 eps
+  let (eps, _) = entropies.iter()
+    .cloned()
+    .fold(None, |best: Option<(f64, f64)>, (eps, entropy)| match best {
+      Some((_, best_entropy)) if best_entropy <= entropy => best,
+      _ => Some((eps, entropy))
+    })?;
+
+  let sizes = neighbourhood_sizes(eps);
+  let avg_neighbourhood_size = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+
+  Some(ParameterEstimate {
+    eps,
+    min_lns: (avg_neighbourhood_size + MIN_LNS_MARGIN).round() as usize,
+    avg_neighbourhood_size,
+    entropies
+  })
+}
+
+/// 计算邻域大小分布的熵
+fn compute_entropy(neighbourhood_sizes: &[usize]) -> f64 {
+  let total = neighbourhood_sizes.iter().sum::<usize>() as f64;
+  if total == 0.0 { return 0.0; }
+
+  neighbourhood_sizes.iter()
+    .filter(|size| **size > 0)
+    .map(|size| {
+      let p = *size as f64 / total;
+      -p * p.log2()
+    })
+    .sum()
+}
@@ -0,0 +1,114 @@
+//! 参数扫描，对同一组线段在多组 (eps, MinLns) 下执行聚类并汇总结果
+//!
+//! 轨迹只划分一次，邻域只按照最大的 eps 计算一次，
+//! 较小的 eps 与不同的 MinLns 都复用这些邻域信息；
+//! 评估 QMeasure 所需的线段距离同样只计算一次，需要 O(n²) 的内存。
+use crate::{
+  models::{
+    line_segment::LineSegment
+  },
+  dbscan::{
+    find_neighbours,
+    expand_clusters
+  },
+  distance_config::DistanceConfig,
+  evaluation::{
+    evaluate_clustering_with_distances,
+    SquaredDistances
+  },
+  cluster_gen::{
+    construct_line_segment_cluster_with_gamma,
+    construct_cluster,
+    DEFAULT_GAMMA
+  }
+};
+
+/// 一组参数下的聚类结果汇总
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+  eps: f64,
+  min_lns: usize,
+  num_of_clusters: usize,
+  noise_ratio: f64,
+  q_measure: f64
+}
+
+impl SweepResult {
+  /// 获得该组参数的 eps
+  pub fn get_eps(&self) -> f64 {
+    self.eps
+  }
+
+  /// 获得该组参数的 MinLns
+  pub fn get_min_lns(&self) -> usize {
+    self.min_lns
+  }
+
+  /// 获得生成了代表轨迹的簇的数量
+  pub fn get_num_of_clusters(&self) -> usize {
+    self.num_of_clusters
+  }
+
+  /// 获得噪声线段占所有线段的比例
+  pub fn get_noise_ratio(&self) -> f64 {
+    self.noise_ratio
+  }
+
+  /// 获得聚类的 QMeasure，越小越好
+  pub fn get_q_measure(&self) -> f64 {
+    self.q_measure
+  }
+}
+
+/// 对所有 (eps, MinLns) 的组合执行聚类，结果按照 eps 优先、MinLns 其次的顺序排列
+///
+/// `min_trajectories` 为 None 时参与轨迹数的阈值与 MinLns 相同，代表轨迹使用默认的平滑参数 γ。
+/// 所有计算都在当前的线程池中执行，需要指定线程数时在 `ThreadPool::install` 中调用。
+pub fn sweep_parameters(line_segments: &[LineSegment], config: &DistanceConfig,
+  eps_values: &[f64], min_lns_values: &[usize], min_trajectories: Option<usize>) -> Vec<SweepResult>
+{
+  sweep_parameters_with_gamma(line_segments, config, eps_values, min_lns_values, min_trajectories, DEFAULT_GAMMA)
+}
+
+/// 使用指定的平滑参数 γ 扫描参数，与聚类时使用的 γ 一致
+pub fn sweep_parameters_with_gamma(line_segments: &[LineSegment], config: &DistanceConfig,
+  eps_values: &[f64], min_lns_values: &[usize], min_trajectories: Option<usize>, gamma: f64) -> Vec<SweepResult>
+{
+  let max_eps = eps_values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  if line_segments.is_empty() || !max_eps.is_finite() { return Vec::new(); }
+
+  let max_neighbours = find_neighbours(max_eps, line_segments, config);
+  let distances = SquaredDistances::new(line_segments, config);
+  let mut results: Vec<SweepResult> = Vec::with_capacity(eps_values.len() * min_lns_values.len());
+
+  for eps in eps_values {
+    let neighbours: Vec<Vec<usize>> = max_neighbours.iter()
+      .map(|neighbour| {
+        neighbour.iter()
+          .filter(|(_, distance)| *distance <= *eps)
+          .map(|(index, _)| *index)
+          .collect()
+      })
+      .collect();
+
+    for min_lns in min_lns_values {
+      let min_trajectories = min_trajectories.unwrap_or(*min_lns);
+      let (cluster_indexs, cluster_index) = expand_clusters(&neighbours, *min_lns, min_trajectories, line_segments);
+
+      let line_segment_clusters = construct_line_segment_cluster_with_gamma(cluster_index, *min_lns, gamma,
+        &cluster_indexs, line_segments, config);
+      let clusters = construct_cluster(line_segment_clusters);
+      let num_of_noise = cluster_indexs.iter().filter(|label| **label < 0).count();
+
+      results.push(SweepResult {
+        eps: *eps,
+        min_lns: *min_lns,
+        num_of_clusters: clusters.len(),
+        noise_ratio: num_of_noise as f64 / line_segments.len() as f64,
+        q_measure: evaluate_clustering_with_distances(&distances, &cluster_indexs, cluster_index).get_q_measure()
+      });
+    }
+  }
+
+  results
+}
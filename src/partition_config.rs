@@ -0,0 +1,111 @@
+//! 轨迹划分的配置
+//!
+//! 默认值针对论文中的数据集，坐标的单位与其相近时才合适。
+//! 对于米级的行人数据应当降低最短线段长度与精度，否则几乎所有线段都会被舍弃；
+//! 对于以度为单位的数据，长度与精度都应当换算到度。
+
+/// 默认的 MDL 代价优势，不划分的代价比划分的代价至少小这么多时才会划分
+pub static DEFAULT_MDL_COST_ADVANTAGE: usize = 25;
+/// 默认的最短线段长度，划分时不会产生更短的线段，轨迹末尾剩下的更短线段不参与聚类
+pub static DEFAULT_MIN_LINE_SEGMENT_LENGTH: f64 = 50.0;
+/// 默认的编码精度
+pub static DEFAULT_PRECISION: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PartitionConfig {
+  mdl_cost_advantage: usize,
+  min_line_segment_length: f64,
+  length_precision: f64,
+  perpendicular_precision: f64,
+  angle_precision: f64
+}
+
+impl Default for PartitionConfig {
+  fn default() -> Self {
+    Self::new(DEFAULT_MDL_COST_ADVANTAGE, DEFAULT_MIN_LINE_SEGMENT_LENGTH)
+  }
+}
+
+impl PartitionConfig {
+  /// 创建一个划分配置，精度使用默认值
+  pub fn new(mdl_cost_advantage: usize, min_line_segment_length: f64) -> Self {
+    Self {
+      mdl_cost_advantage,
+      min_line_segment_length,
+      length_precision: DEFAULT_PRECISION,
+      perpendicular_precision: DEFAULT_PRECISION,
+      angle_precision: DEFAULT_PRECISION
+    }
+  }
+
+  /// 获得 MDL 代价优势，优势越大划分点越少
+  pub fn get_mdl_cost_advantage(&self) -> usize {
+    self.mdl_cost_advantage
+  }
+
+  /// 获得最短线段长度，MDL 算法不会在离上一个划分点更近的位置划分
+  pub fn get_min_line_segment_length(&self) -> f64 {
+    self.min_line_segment_length
+  }
+
+  /// 获得 L(H) 中线段长度的精度，更短的线段代价为 0
+  pub fn get_length_precision(&self) -> f64 {
+    self.length_precision
+  }
+
+  /// 获得 L(D|H) 中垂直距离的精度，更小的距离按照该精度编码
+  pub fn get_perpendicular_precision(&self) -> f64 {
+    self.perpendicular_precision
+  }
+
+  /// 获得 L(D|H) 中角度距离的精度，更小的距离按照该精度编码
+  pub fn get_angle_precision(&self) -> f64 {
+    self.angle_precision
+  }
+
+  /// 设置 MDL 代价优势
+  pub fn set_mdl_cost_advantage(&mut self, mdl_cost_advantage: usize) {
+    self.mdl_cost_advantage = mdl_cost_advantage;
+  }
+
+  /// 设置最短线段长度，经纬度坐标下单位为米
+  pub fn set_min_line_segment_length(&mut self, min_line_segment_length: f64) {
+    self.min_line_segment_length = min_line_segment_length;
+  }
+
+  /// 设置 L(H) 中线段长度的精度
+  pub fn set_length_precision(&mut self, precision: f64) {
+    self.length_precision = precision;
+  }
+
+  /// 设置 L(D|H) 中垂直距离的精度
+  pub fn set_perpendicular_precision(&mut self, precision: f64) {
+    self.perpendicular_precision = precision;
+  }
+
+  /// 设置 L(D|H) 中角度距离的精度
+  pub fn set_angle_precision(&mut self, precision: f64) {
+    self.angle_precision = precision;
+  }
+
+  /// 将三种精度设置为同一个值
+  pub fn set_precision(&mut self, precision: f64) {
+    self.length_precision = precision;
+    self.perpendicular_precision = precision;
+    self.angle_precision = precision;
+  }
+
+  /// 检查配置是否有效，无效时返回参数名
+  pub fn validate(&self) -> Result<(), &'static str> {
+    let is_positive = |value: f64| value.is_finite() && value > 0.0;
+
+    if !self.min_line_segment_length.is_finite() || self.min_line_segment_length < 0.0 {
+      return Err("min_line_segment_length");
+    }
+    if !is_positive(self.length_precision) { return Err("length_precision"); }
+    if !is_positive(self.perpendicular_precision) { return Err("perpendicular_precision"); }
+    if !is_positive(self.angle_precision) { return Err("angle_precision"); }
+
+    Ok(())
+  }
+}
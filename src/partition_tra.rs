@@ -3,30 +3,43 @@ use crate::{
     trajectory::Trajectory,
     thick_trajectory::ThickTrajectory,
     point::Point,
-    line_segment::LineSegment
+    line_segment::{LineSegment, SegmentId}
   },
   distance_util::{
-    measure_distance_point_to_point,
+    measure_distance,
     measure_perpendicular_distance,
-    measure_angle_distance
-  }
+    measure_angle_distance,
+    localize_lines
+  },
+  distance_config::{
+    DistanceConfig,
+    AngleMode
+  },
+  partition_config::PartitionConfig
 };
 use std::collections::HashSet;
 
-static MDL_COST_ADWANTAGE: usize = 25;
-static MIN_LINE_SEGMENT_LENGTH: f64 = 50.0;
-
 /// 将轨迹抽象为划分轨迹
-pub fn partition_trajectories(trajectories: Vec<Trajectory>) -> Vec<ThickTrajectory> {
+///
+/// 距离按照配置中的坐标含义计算，经纬度坐标下 MDL 代价以米为单位；
+/// MDL 代价优势与编码精度由划分配置给出，离上一个划分点不足最短线段长度的点不会成为划分点
+pub fn partition_trajectories(trajectories: Vec<Trajectory>, config: &DistanceConfig,
+  partition_config: &PartitionConfig) -> Vec<ThickTrajectory>
+{
   // 直接所有权转移
   trajectories.into_iter()
-    .map(|trajectory| partition_trajectory(trajectory))
+    .map(|trajectory| partition_trajectory(trajectory, config, partition_config))
     .collect()
 }
 
-/// 划分单条轨迹
-fn partition_trajectory(trajectory: Trajectory) -> ThickTrajectory {
+/// 划分单条轨迹，没有轨迹点时得到没有划分点的轨迹
+fn partition_trajectory(trajectory: Trajectory, config: &DistanceConfig,
+  partition_config: &PartitionConfig) -> ThickTrajectory
+{
   let len = trajectory.get_points_len();
+  if len == 0 {
+    return ThickTrajectory::new(trajectory.get_id(), trajectory.get_part(), Vec::new(), Vec::new());
+  }
   let mut partition_indexs = HashSet::new();
 
   // 添加起点到划分点中
@@ -46,19 +59,29 @@ fn partition_trajectory(trajectory: Trajectory) -> ThickTrajectory {
       let end_index = start_index + length;
       no_par_cost += compute_model_cost(
         trajectory.get_point(end_index - 1).unwrap(), 
-        trajectory.get_point(end_index).unwrap()
+        trajectory.get_point(end_index).unwrap(),
+        config,
+        partition_config
       );
 
       par_cost = compute_model_cost(
         trajectory.get_point(start_index).unwrap(), 
-        trajectory.get_point(end_index).unwrap()
+        trajectory.get_point(end_index).unwrap(),
+        config,
+        partition_config
       ) + compute_encoding_cost(
         &trajectory,
         start_index, 
-        end_index
+        end_index,
+        config,
+        partition_config
       );
 
-      if no_par_cost + MDL_COST_ADWANTAGE < par_cost {
+      // 划分得到的线段短于最短线段长度时不划分，短线段并入之后的线段中
+      if no_par_cost + partition_config.get_mdl_cost_advantage() < par_cost
+        && measure_distance(trajectory.get_point(start_index).unwrap(), trajectory.get_point(end_index - 1).unwrap(),
+          config) >= partition_config.get_min_line_segment_length()
+      {
         partition_indexs.insert(end_index - 1);
         start_index = end_index - 1;
         length = 0;
@@ -75,31 +98,34 @@ fn partition_trajectory(trajectory: Trajectory) -> ThickTrajectory {
   partition_indexs.insert(len - 1);
 
   let id = trajectory.get_id();
+  let part = trajectory.get_part();
   let points = trajectory.get_points();
-  // 获得所有的轨迹点
-  let partition_points: Vec<Point> = points.into_iter()
+  // 获得所有的轨迹点以及它们在原轨迹中的序号
+  let (point_indexs, partition_points): (Vec<usize>, Vec<Point>) = points.into_iter()
     .enumerate()
-    .filter_map(|(index, point)| {
-      if partition_indexs.contains(&index) { 
-        return Some(point); 
-      }
-      else { return None; }
-    })
-    .collect();
+    .filter(|(index, _)| partition_indexs.contains(index))
+    .unzip();
 
-  ThickTrajectory::new(id, partition_points)
+  ThickTrajectory::new(id, part, partition_points, point_indexs)
 }
 
-/// 计算 L(H)
-fn compute_model_cost(start_point: &Point, end_point: &Point) -> usize {
-  let distance = measure_distance_point_to_point(start_point, end_point);
-  if distance < 1.0 { return 0; }
+/// 计算 L(H)，长度按照配置的精度编码
+fn compute_model_cost(start_point: &Point, end_point: &Point, config: &DistanceConfig,
+  partition_config: &PartitionConfig) -> usize
+{
+  let precision = partition_config.get_length_precision();
+  let distance = measure_distance(start_point, end_point, config);
+  if distance < precision { return 0; }
 
-  distance.log2().ceil() as usize
+  (distance / precision).log2().ceil() as usize
 }
 
-// 计算 L(D|H)
-fn compute_encoding_cost(trajectory: &Trajectory, start_index: usize, end_index: usize) -> usize {
+// 计算 L(D|H)，小于精度的距离按照精度编码
+fn compute_encoding_cost(trajectory: &Trajectory, start_index: usize, end_index: usize,
+  config: &DistanceConfig, partition_config: &PartitionConfig) -> usize
+{
+  let perpendicular_precision = partition_config.get_perpendicular_precision();
+  let angle_precision = partition_config.get_angle_precision();
   let start_point = trajectory.get_point(start_index).unwrap();
   let end_point = trajectory.get_point(end_index).unwrap();
   let mut encoding_cost: usize = 0;
@@ -108,32 +134,41 @@ fn compute_encoding_cost(trajectory: &Trajectory, start_index: usize, end_index:
     let line_start_point = trajectory.get_point(i).unwrap();
     let line_end_point = trajectory.get_point(i + 1).unwrap();
 
-    let mut perpendicular_distance = measure_perpendicular_distance(start_point, end_point, line_start_point, line_end_point);
-    let mut angle_distance = measure_angle_distance(start_point, end_point, line_start_point, line_end_point);
+    let [start_point, end_point, line_start_point, line_end_point] =
+      localize_lines(start_point, end_point, line_start_point, line_end_point, config);
 
-    if perpendicular_distance < 1.0 { perpendicular_distance = 1.0; }
-    if angle_distance < 1.0 { angle_distance = 1.0; }
+    let perpendicular_distance = measure_perpendicular_distance(&start_point, &end_point, &line_start_point, &line_end_point)
+      .max(perpendicular_precision);
+    let angle_distance = measure_angle_distance(&start_point, &end_point, &line_start_point, &line_end_point, AngleMode::Undirected)
+      .max(angle_precision);
 
-    encoding_cost += (perpendicular_distance.log2().ceil() + angle_distance.log2().ceil()) as usize;
+    encoding_cost += ((perpendicular_distance / perpendicular_precision).log2().ceil()
+      + (angle_distance / angle_precision).log2().ceil()) as usize;
   }
 
   encoding_cost
 }
 
-/// 将轨迹的划分点相连成为线段存入数组中
-pub fn get_partition_line(trajectories: &Vec<ThickTrajectory>) -> Vec<LineSegment> {
+/// 将轨迹的划分点相连成为线段存入数组中，短于配置的最短线段长度的线段（只可能在轨迹末尾或整条轨迹过短时出现）会被舍弃
+///
+/// 线段拥有自己的端点，不依赖于划分后轨迹，可以单独保存后重新聚类
+pub fn get_partition_line(trajectories: &[ThickTrajectory], config: &DistanceConfig,
+  partition_config: &PartitionConfig) -> Vec<LineSegment>
+{
   let mut line_segments = Vec::new();
 
   for trajectory in trajectories.iter() {
-    for i in 0..(trajectory.get_len() - 1) {
+    for i in 0..trajectory.get_len().saturating_sub(1) {
       let start_point = trajectory.get_partition_point(i).unwrap();
       let end_point = trajectory.get_partition_point(i + 1).unwrap();
 
-      if measure_distance_point_to_point(start_point, end_point) < MIN_LINE_SEGMENT_LENGTH {
+      if measure_distance(start_point, end_point, config) < partition_config.get_min_line_segment_length() {
         continue;
       }
 
-      let line_segment = LineSegment::new(trajectory.get_id(), start_point, end_point);
+      let point_range = (trajectory.get_point_index(i).unwrap(), trajectory.get_point_index(i + 1).unwrap());
+      let id = SegmentId::new(trajectory.get_id(), trajectory.get_part(), i);
+      let line_segment = LineSegment::new(id, point_range, *start_point, *end_point);
       line_segments.push(line_segment);
     }
   }
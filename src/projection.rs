@@ -0,0 +1,219 @@
+//! 坐标投影预处理，将经纬度轨迹投影到以米为单位的平面坐标系中
+//!
+//! 投影后的轨迹可以直接使用平面距离进行划分与聚类，
+//! 得到的代表轨迹再还原为经纬度。x 为经度、y 为纬度，单位为度，z 与时间戳保持不变。
+use crate::{
+  models::{
+    point::Point,
+    trajectory::Trajectory,
+    line_segment::LineSegment,
+    cluster::Cluster
+  },
+  distance_util::{
+    compute_geographic_centroid,
+    project_to_local_plane,
+    unproject_from_local_plane
+  }
+};
+
+/// WGS84 椭球长半轴，单位为米
+static WGS84_A: f64 = 6_378_137.0;
+/// WGS84 椭球扁率
+static WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// UTM 中央经线的比例因子
+static UTM_K0: f64 = 0.9996;
+/// UTM 的东偏移，单位为米
+static UTM_FALSE_EASTING: f64 = 500_000.0;
+/// 南半球 UTM 的北偏移，单位为米
+static UTM_FALSE_NORTHING: f64 = 10_000_000.0;
+
+/// 投影方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionKind {
+  // 根据数据中心自动选择 UTM 分带
+  Utm,
+  // 以数据中心为原点的局部东-北坐标系
+  LocalEnu,
+}
+
+/// 一个确定的投影
+#[derive(Debug, Clone, Copy)]
+pub enum Projection {
+  // UTM 投影，zone 为 1 到 60 的分带号，north 表示北半球
+  Utm { zone: u8, north: bool },
+  // 局部东-北坐标系，origin 为原点的经纬度
+  LocalEnu { origin: Point },
+}
+
+impl Projection {
+  /// 创建一个 UTM 投影
+  pub fn utm(zone: u8, north: bool) -> Self {
+    assert!((1..=60).contains(&zone), "UTM zone {} doesn't exist", zone);
+
+    Projection::Utm { zone, north }
+  }
+
+  /// 创建一个以指定经纬度为原点的局部东-北坐标系
+  pub fn local_enu(lon: f64, lat: f64) -> Self {
+    Projection::LocalEnu { origin: Point::new(lon, lat) }
+  }
+
+  /// 根据所有轨迹点的中心选择投影，没有任何轨迹点时为 None
+  pub fn from_trajectories(kind: ProjectionKind, trajectories: &[Trajectory]) -> Option<Self> {
+    let points: Vec<&Point> = trajectories.iter()
+      .flat_map(|trajectory| (0..trajectory.get_points_len()).filter_map(move |i| trajectory.get_point(i)))
+      .collect();
+    if points.is_empty() { return None; }
+
+    let centroid = compute_geographic_centroid(&points);
+    let lon = (centroid.get_x() + 180.0).rem_euclid(360.0) - 180.0;
+
+    match kind {
+      ProjectionKind::Utm => {
+        let zone = (((lon + 180.0) / 6.0).floor() as u8 + 1).min(60);
+        Some(Self::utm(zone, centroid.get_y() >= 0.0))
+      },
+      ProjectionKind::LocalEnu => Some(Self::local_enu(lon, centroid.get_y()))
+    }
+  }
+
+  /// 将经纬度点投影到平面坐标系中
+  pub fn project(&self, point: &Point) -> Point {
+    match self {
+      Projection::Utm { zone, north } => project_to_utm(point, *zone, *north),
+      Projection::LocalEnu { origin } => project_to_local_plane(point, origin)
+    }
+  }
+
+  /// 将平面坐标系中的点还原为经纬度点
+  pub fn unproject(&self, point: &Point) -> Point {
+    match self {
+      Projection::Utm { zone, north } => unproject_from_utm(point, *zone, *north),
+      Projection::LocalEnu { origin } => unproject_from_local_plane(point, origin)
+    }
+  }
+}
+
+/// 投影所有轨迹，轨迹 id 与点的顺序保持不变
+pub fn project_trajectories(trajectories: Vec<Trajectory>, projection: &Projection) -> Vec<Trajectory> {
+  trajectories.into_iter()
+    .map(|trajectory| {
+      let mut projected = Trajectory::new(trajectory.get_id());
+      projected.set_part(trajectory.get_part());
+      for point in trajectory.get_points() {
+        projected.add_point(projection.project(&point));
+      }
+      projected
+    })
+    .collect()
+}
+
+/// 将所有簇的代表轨迹还原为经纬度
+pub fn unproject_clusters(clusters: Vec<Cluster>, projection: &Projection) -> Vec<Cluster> {
+  clusters.into_iter()
+    .map(|mut cluster| {
+      let points: Vec<Point> = cluster.get_points().iter()
+        .map(|point| projection.unproject(point))
+        .collect();
+      cluster.set_points(points);
+      cluster
+    })
+    .collect()
+}
+
+/// 将所有线段的端点还原为经纬度，顺序与 `line_segments` 一致
+pub fn unproject_line_segments(line_segments: &[LineSegment], projection: &Projection) -> Vec<LineSegment> {
+  line_segments.iter()
+    .map(|line_segment| {
+      let (start_point, end_point) = line_segment.extract_start_end_points();
+      line_segment.with_points(projection.unproject(start_point), projection.unproject(end_point))
+    })
+    .collect()
+}
+
+/// 获得 WGS84 椭球的第一偏心率平方与第二偏心率平方
+fn eccentricities() -> (f64, f64) {
+  let e2 = WGS84_F * (2.0 - WGS84_F);
+
+  (e2, e2 / (1.0 - e2))
+}
+
+/// 获得 UTM 分带的中央经线，单位为弧度
+fn central_meridian(zone: u8) -> f64 {
+  (f64::from(zone) * 6.0 - 183.0).to_radians()
+}
+
+/// 使用 Snyder 的级数公式将经纬度点投影到 UTM 坐标系中
+fn project_to_utm(point: &Point, zone: u8, north: bool) -> Point {
+  let (e2, ep2) = eccentricities();
+  let lat = point.get_y().to_radians();
+  let diff_lon = ((point.get_x().to_radians() - central_meridian(zone)).to_degrees() + 180.0)
+    .rem_euclid(360.0) - 180.0;
+
+  let n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+  let t = lat.tan().powi(2);
+  let c = ep2 * lat.cos().powi(2);
+  let a = lat.cos() * diff_lon.to_radians();
+  let m = meridian_arc(lat, e2);
+
+  let easting = UTM_K0 * n * (a
+    + (1.0 - t + c) * a.powi(3) / 6.0
+    + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0)
+    + UTM_FALSE_EASTING;
+  let mut northing = UTM_K0 * (m + n * lat.tan() * (a * a / 2.0
+    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+  if !north { northing += UTM_FALSE_NORTHING; }
+
+  let mut projected = *point;
+  projected.set_x(easting);
+  projected.set_y(northing);
+
+  projected
+}
+
+/// 将 UTM 坐标系中的点还原为经纬度点，是 `project_to_utm` 的逆运算
+fn unproject_from_utm(point: &Point, zone: u8, north: bool) -> Point {
+  let (e2, ep2) = eccentricities();
+  let northing = if north { point.get_y() } else { point.get_y() - UTM_FALSE_NORTHING };
+
+  let m = northing / UTM_K0;
+  let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+  let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+  let lat_1 = mu
+    + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+    + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+    + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+    + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+  let c_1 = ep2 * lat_1.cos().powi(2);
+  let t_1 = lat_1.tan().powi(2);
+  let n_1 = WGS84_A / (1.0 - e2 * lat_1.sin().powi(2)).sqrt();
+  let r_1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * lat_1.sin().powi(2)).powf(1.5);
+  let d = (point.get_x() - UTM_FALSE_EASTING) / (n_1 * UTM_K0);
+
+  let lat = lat_1 - (n_1 * lat_1.tan() / r_1) * (d * d / 2.0
+    - (5.0 + 3.0 * t_1 + 10.0 * c_1 - 4.0 * c_1 * c_1 - 9.0 * ep2) * d.powi(4) / 24.0
+    + (61.0 + 90.0 * t_1 + 298.0 * c_1 + 45.0 * t_1 * t_1 - 252.0 * ep2 - 3.0 * c_1 * c_1) * d.powi(6) / 720.0);
+  let lon = central_meridian(zone) + (d
+    - (1.0 + 2.0 * t_1 + c_1) * d.powi(3) / 6.0
+    + (5.0 - 2.0 * c_1 + 28.0 * t_1 - 3.0 * c_1 * c_1 + 8.0 * ep2 + 24.0 * t_1 * t_1) * d.powi(5) / 120.0)
+    / lat_1.cos();
+
+  let mut unprojected = *point;
+  unprojected.set_x((lon.to_degrees() + 180.0).rem_euclid(360.0) - 180.0);
+  unprojected.set_y(lat.to_degrees());
+
+  unprojected
+}
+
+/// 计算赤道到指定纬度的子午线弧长
+fn meridian_arc(lat: f64, e2: f64) -> f64 {
+  let e4 = e2 * e2;
+  let e6 = e4 * e2;
+
+  WGS84_A * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+    - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+    + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+    - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
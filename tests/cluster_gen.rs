@@ -0,0 +1,89 @@
+//! 代表轨迹生成的测试
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    line_segment::{LineSegment, SegmentId},
+    cluster::Cluster
+  },
+  distance_config::DistanceConfig,
+  cluster_gen::{
+    construct_line_segment_cluster,
+    construct_cluster,
+    filter_clusters_by_time
+  }
+};
+
+fn line_segment(id: usize, start: (f64, f64), end: (f64, f64)) -> LineSegment {
+  LineSegment::new(SegmentId::new(id, 0, 0), (0, 1), Point::new(start.0, start.1), Point::new(end.0, end.1))
+}
+
+#[test]
+fn clusters_without_representative_points_are_dropped() {
+  let line_segments = vec![
+    // 簇 0：三条相互重叠的平行线段
+    line_segment(0, (0.0, 0.0), (300.0, 0.0)),
+    line_segment(1, (0.0, 5.0), (300.0, 5.0)),
+    line_segment(2, (0.0, 10.0), (300.0, 10.0)),
+    // 簇 1：三条首尾相隔的线段，扫描时同时覆盖的线段始终少于 MinLns
+    line_segment(3, (0.0, 500.0), (100.0, 500.0)),
+    line_segment(4, (150.0, 500.0), (250.0, 500.0)),
+    line_segment(5, (300.0, 500.0), (400.0, 500.0)),
+    // 簇 2：与簇 0 相同，但位于更远的地方
+    line_segment(6, (0.0, 1000.0), (300.0, 1000.0)),
+    line_segment(7, (0.0, 1005.0), (300.0, 1005.0)),
+    line_segment(8, (0.0, 1010.0), (300.0, 1010.0))
+  ];
+  let cluster_indexs = vec![0, 0, 0, 1, 1, 1, 2, 2, 2];
+
+  let line_segment_clusters = construct_line_segment_cluster(3, 3, &cluster_indexs, &line_segments,
+    &DistanceConfig::default());
+  let enables: Vec<bool> = line_segment_clusters.iter().map(|cluster| cluster.get_enable()).collect();
+  assert_eq!(enables, vec![true, false, true]);
+
+  // 剩下的簇重新编号，仍然记录原来的簇索引
+  let clusters = construct_cluster(line_segment_clusters);
+  let ids: Vec<(usize, usize)> = clusters.iter()
+    .map(|cluster| (cluster.get_id(), cluster.get_line_segment_cluster_id()))
+    .collect();
+  assert_eq!(ids, vec![(0, 0), (1, 2)]);
+  assert!(clusters.iter().all(|cluster| cluster.get_len() >= 2));
+}
+
+#[test]
+fn time_window_bounds_are_inclusive() {
+  let time_ranges = [Some((0.0, 10.0)), Some((10.0, 20.0)), Some((15.0, 18.0)), Some((20.0, 30.0)),
+    Some((30.5, 40.0)), None];
+  let clusters = || -> Vec<Cluster> {
+    time_ranges.iter()
+      .enumerate()
+      .map(|(id, time_range)| {
+        let mut cluster = Cluster::new(id, id, 3, vec![0, 1, 2], vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)]);
+        cluster.set_time_range(*time_range);
+        cluster
+      })
+      .collect()
+  };
+  let ids = |clusters: Vec<Cluster>| -> Vec<usize> { clusters.iter().map(|cluster| cluster.get_id()).collect() };
+
+  // 恰好在时间段端点结束或开始的簇也与时间段重叠，没有时间戳的簇总是被过滤掉，保留的簇 id 不变
+  assert_eq!(ids(filter_clusters_by_time(clusters(), 10.0, 30.0)), vec![0, 1, 2, 3]);
+  assert_eq!(ids(filter_clusters_by_time(clusters(), 10.0, 10.0)), vec![0, 1]);
+  assert_eq!(ids(filter_clusters_by_time(clusters(), 30.1, 30.4)), Vec::<usize>::new());
+  assert_eq!(ids(filter_clusters_by_time(clusters(), f64::NEG_INFINITY, f64::INFINITY)), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn clusters_without_timestamps_have_no_time_range() {
+  let line_segments: Vec<LineSegment> = (0..3)
+    .map(|id| line_segment(id, (0.0, id as f64 * 5.0), (300.0, id as f64 * 5.0)))
+    .collect();
+
+  let clusters = construct_cluster(construct_line_segment_cluster(1, 3, &[0, 0, 0], &line_segments,
+    &DistanceConfig::default()));
+
+  assert_eq!(clusters.len(), 1);
+  assert_eq!(clusters[0].get_time_range(), None);
+  assert!(filter_clusters_by_time(clusters, f64::NEG_INFINITY, f64::INFINITY).is_empty());
+}
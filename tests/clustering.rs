@@ -0,0 +1,152 @@
+//! TraClus 完整流程的测试
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    trajectory::Trajectory,
+    line_segment::{LineSegment, SegmentId}
+  },
+  distance_config::{AngleMode, DistanceConfig},
+  clustering::{TraClus, TraClusError}
+};
+
+/// 几条相互平行的轨迹，聚类后得到一个簇
+fn parallel_trajectories() -> Vec<Trajectory> {
+  (0..4)
+    .map(|id| {
+      let mut trajectory = Trajectory::new(id);
+      for i in 0..5 {
+        trajectory.add_point(Point::new(i as f64 * 100.0, id as f64 * 5.0));
+      }
+      trajectory
+    })
+    .collect()
+}
+
+#[test]
+fn empty_trajectories_are_skipped() {
+  let mut trajectories = parallel_trajectories();
+  trajectories.insert(0, Trajectory::new(10));
+  let mut single = Trajectory::new(11);
+  single.add_point(Point::new(0.0, 0.0));
+  trajectories.push(single);
+
+  let result = TraClus::new(30.0, 3).run(trajectories).unwrap();
+  let expected = TraClus::new(30.0, 3).run(parallel_trajectories()).unwrap();
+
+  assert_eq!(result.get_statistics().get_num_of_trajectories(), 6);
+  assert_eq!(result.get_statistics().get_num_of_line_segments(), expected.get_statistics().get_num_of_line_segments());
+  assert_eq!(result.get_cluster_indexs(), expected.get_cluster_indexs());
+  assert_eq!(result.get_thick_trajectories()[0].get_len(), 0);
+}
+
+#[test]
+fn only_empty_trajectories() {
+  let result = TraClus::new(30.0, 3).run(vec![Trajectory::new(0), Trajectory::new(1)]).unwrap();
+
+  assert_eq!(result.get_statistics().get_num_of_line_segments(), 0);
+  assert!(result.get_clusters().is_empty());
+}
+
+#[test]
+fn invalid_weights_are_rejected() {
+  let weights = [(-1.0, 1.0, 1.0), (1.0, f64::NAN, 1.0), (1.0, 1.0, -0.5), (1.0, 1.0, f64::INFINITY)];
+  for (perpendicular_weight, parallel_weight, angle_weight) in weights.iter() {
+    let mut traclus = TraClus::new(30.0, 3);
+    traclus.set_weights(*perpendicular_weight, *parallel_weight, *angle_weight);
+
+    match traclus.run(parallel_trajectories()) {
+      Err(TraClusError::InvalidParameter(_)) => {},
+      _ => panic!("weights {:?} should be rejected", (perpendicular_weight, parallel_weight, angle_weight))
+    }
+  }
+
+  let mut traclus = TraClus::new(30.0, 3);
+  traclus.set_weights(0.0, 1.0, 0.0);
+  assert!(traclus.run(parallel_trajectories()).is_ok());
+}
+
+/// 四条向东的轨迹与三条向西的轨迹相互平行，起点都在 x = 0 或 x = 400
+fn opposite_flows() -> Vec<Trajectory> {
+  (0..7)
+    .map(|id| {
+      let mut trajectory = Trajectory::new(id);
+      for i in 0..5 {
+        let x = if id < 4 { i as f64 * 100.0 } else { 400.0 - i as f64 * 100.0 };
+        trajectory.add_point(Point::new(x, id as f64 * 2.5));
+      }
+      trajectory
+    })
+    .collect()
+}
+
+fn run_with_angle_mode(angle_mode: AngleMode) -> Vec<(Vec<usize>, Vec<f64>)> {
+  let mut distance_config = DistanceConfig::default();
+  distance_config.set_angle_mode(angle_mode);
+  let mut traclus = TraClus::new(30.0, 3);
+  traclus.set_distance_config(distance_config);
+
+  traclus.run(opposite_flows()).unwrap()
+    .get_clusters()
+    .iter()
+    .map(|cluster| {
+      let xs = cluster.get_points().iter().map(|point| point.get_x()).collect();
+      (cluster.get_trajectory_ids().clone(), xs)
+    })
+    .collect()
+}
+
+#[test]
+fn opposite_flows_merge_when_undirected() {
+  assert_eq!(DistanceConfig::default().get_angle_mode(), AngleMode::Undirected);
+
+  let clusters = run_with_angle_mode(AngleMode::Undirected);
+
+  assert_eq!(clusters.len(), 1);
+  assert_eq!(clusters[0].0, vec![0, 1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn opposite_flows_split_when_directed() {
+  let clusters = run_with_angle_mode(AngleMode::Directed);
+
+  assert_eq!(clusters.len(), 2);
+  assert_eq!(clusters[0].0, vec![0, 1, 2, 3]);
+  assert_eq!(clusters[1].0, vec![4, 5, 6]);
+
+  // 向东的簇投影后从 0 开始，第一个扫描位置同样会生成代表点；
+  // 两个簇的代表轨迹各自沿着自己的方向
+  let (east, west) = (&clusters[0].1, &clusters[1].1);
+  assert!(east[0].abs() < 1e-9, "{:?}", east);
+  assert!(east.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", east);
+  assert!((west[0] - 400.0).abs() < 1e-9, "{:?}", west);
+  assert!(west.windows(2).all(|pair| pair[0] > pair[1]), "{:?}", west);
+}
+
+/// 两束平行线段，每束四条：第一束来自轨迹 0、1，第二束来自轨迹 2 到 5
+fn bundles_with_trajectory_ids() -> Vec<LineSegment> {
+  [(0, 0.0), (0, 5.0), (1, 10.0), (1, 15.0), (2, 1000.0), (3, 1005.0), (4, 1010.0), (5, 1015.0)].iter()
+    .enumerate()
+    .map(|(i, (trajectory_id, y))| {
+      LineSegment::new(SegmentId::new(*trajectory_id, 0, i), (0, 1), Point::new(0.0, *y), Point::new(100.0, *y))
+    })
+    .collect()
+}
+
+#[test]
+fn clusters_need_enough_distinct_trajectories() {
+  let labels = |min_trajectories: Option<usize>| -> Vec<i32> {
+    let mut traclus = TraClus::new(30.0, 3);
+    traclus.set_min_trajectories(min_trajectories);
+    traclus.cluster_line_segments(bundles_with_trajectory_ids()).unwrap().get_cluster_indexs().clone()
+  };
+
+  // 阈值恰好等于参与轨迹数时簇仍然保留
+  assert_eq!(labels(Some(2)), vec![0, 0, 0, 0, 1, 1, 1, 1]);
+  // 第一束的四条线段足够构成簇，但只来自两条轨迹，成为噪声
+  assert_eq!(labels(Some(3)), vec![-1, -1, -1, -1, 0, 0, 0, 0]);
+  assert_eq!(labels(None), labels(Some(3)));
+  assert_eq!(labels(Some(4)), vec![-1, -1, -1, -1, 0, 0, 0, 0]);
+  assert_eq!(labels(Some(5)), vec![-1; 8]);
+}
@@ -0,0 +1,66 @@
+//! QMeasure 的测试，期望值都是手工计算的
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    line_segment::{LineSegment, SegmentId}
+  },
+  distance_config::DistanceConfig,
+  evaluation::evaluate_clustering
+};
+
+/// 长度为 100 的水平线段，起点为 (x, y)
+fn horizontal(id: usize, x: f64, y: f64) -> LineSegment {
+  LineSegment::new(SegmentId::new(id, 0, 0), (0, 1), Point::new(x, y), Point::new(x + 100.0, y))
+}
+
+fn assert_close(actual: f64, expected: f64) {
+  assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+#[test]
+fn sse_and_noise_penalty() {
+  let line_segments = vec![
+    horizontal(0, 0.0, 0.0),
+    horizontal(1, 0.0, 10.0),
+    horizontal(2, 0.0, 40.0),
+    horizontal(3, 0.0, 1000.0),
+    horizontal(4, 0.0, 1006.0),
+    horizontal(5, 0.0, 100.0),
+    horizontal(6, 20.0, 130.0)
+  ];
+  let cluster_indexs = vec![0, 0, 0, 1, 1, -1, -1];
+
+  let quality = evaluate_clustering(&line_segments, &cluster_indexs, 2, &DistanceConfig::default());
+
+  // 平行且对齐的线段之间只有垂直距离：簇 0 中两两距离为 10、40、30，
+  // SSE = 2 · (10² + 40² + 30²) / (2 · 3)
+  assert_close(quality.get_cluster_sse()[0], 5200.0 / 6.0);
+  // 簇 1 中距离为 6，SSE = 2 · 6² / (2 · 2)
+  assert_close(quality.get_cluster_sse()[1], 18.0);
+  assert_close(quality.get_total_sse(), 5200.0 / 6.0 + 18.0);
+  // 两条噪声线段的垂直距离为 30，错开 20 使平行距离为 20，惩罚为 2 · 50² / (2 · 2)
+  assert_close(quality.get_noise_penalty(), 1250.0);
+  assert_close(quality.get_q_measure(), 5200.0 / 6.0 + 18.0 + 1250.0);
+}
+
+#[test]
+fn weights_scale_the_measure() {
+  let line_segments = vec![horizontal(0, 0.0, 0.0), horizontal(1, 0.0, 10.0), horizontal(2, 0.0, 500.0)];
+  let cluster_indexs = vec![0, 0, -1];
+
+  // 单独的噪声线段与自身的距离为 0，没有惩罚
+  let quality = evaluate_clustering(&line_segments, &cluster_indexs, 1, &DistanceConfig::new(2.0, 1.0, 1.0));
+
+  assert_close(quality.get_total_sse(), 2.0 * 20.0 * 20.0 / 4.0);
+  assert_close(quality.get_noise_penalty(), 0.0);
+}
+
+#[test]
+fn empty_clusters_have_no_sse() {
+  let quality = evaluate_clustering(&[], &[], 0, &DistanceConfig::default());
+
+  assert!(quality.get_cluster_sse().is_empty());
+  assert_close(quality.get_q_measure(), 0.0);
+}
@@ -0,0 +1,513 @@
+//! 文件读写的测试
+extern crate traclus;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use serde_json::{json, Value};
+use traclus::{
+  models::{
+    point::Point,
+    trajectory::Trajectory,
+    line_segment::{LineSegment, SegmentId},
+    cluster::Cluster
+  },
+  file_io::{
+    read_trajectory_lines,
+    read_trajectory_lines_with_mode,
+    write_atomic,
+    ErrorMode,
+    FileErrorKind,
+    csv::{
+      read_csv_trajectories,
+      read_csv_trajectories_with_mode,
+      CsvColumn,
+      CsvOptions
+    },
+    geojson::{
+      read_geojson_trajectories,
+      to_feature_collection
+    },
+    labels::{
+      write_labels_json,
+      write_labels_csv_to,
+      write_cluster_members_csv_to
+    }
+  }
+};
+
+/// 创建一个空的临时目录
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("traclus-file-io-{}-{}", std::process::id(), name));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+#[test]
+fn concurrent_atomic_writes_never_interleave() {
+  let dir = temp_dir("concurrent");
+  let out_path = dir.join("out.txt");
+  let chunk = vec![0u8; 4096];
+
+  let handles: Vec<_> = (0..8u8)
+    .map(|i| {
+      let out_path = out_path.clone();
+      let mut chunk = chunk.clone();
+      for byte in chunk.iter_mut() { *byte = b'a' + i; }
+      thread::spawn(move || {
+        write_atomic(out_path.to_str().unwrap(), |writer| {
+          for _ in 0..64 {
+            writer.write_all(&chunk)?;
+          }
+          Ok(())
+        }).unwrap();
+      })
+    })
+    .collect();
+  for handle in handles {
+    handle.join().unwrap();
+  }
+
+  let content = fs::read(&out_path).unwrap();
+  assert_eq!(content.len(), 64 * 4096);
+  assert!(content.iter().all(|byte| *byte == content[0]));
+
+  // 临时文件都已被重命名或删除
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_atomic_write_keeps_the_target() {
+  let dir = temp_dir("failed");
+  let out_path = dir.join("out.txt");
+  fs::write(&out_path, "old").unwrap();
+
+  let result = write_atomic(out_path.to_str().unwrap(), |writer| {
+    writer.write_all(b"new")?;
+    Err(std::io::Error::other("interrupted"))
+  });
+
+  assert!(result.is_err());
+  assert_eq!(fs::read_to_string(&out_path).unwrap(), "old");
+  assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+/// 获得仓库中自带的数据文件路径
+fn data_path(name: &str) -> String {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("data").join(name).to_str().unwrap().to_string()
+}
+
+/// 将内容写入临时文件，返回文件路径
+fn write_temp(name: &str, content: &str) -> String {
+  let path = env::temp_dir().join(format!("traclus-file-io-{}-{}", std::process::id(), name));
+  fs::write(&path, content).unwrap();
+  path.to_str().unwrap().to_string()
+}
+
+/// 获得文件中的所有非空行
+fn read_lines(path: &str) -> Vec<String> {
+  fs::read_to_string(path).unwrap()
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| line.to_string())
+    .collect()
+}
+
+fn point_counts(trajectories: &[Trajectory]) -> Vec<usize> {
+  trajectories.iter().map(Trajectory::get_points_len).collect()
+}
+
+/// 检查带轨迹数量行的文件，每条记录为 `id npoints x y ...`
+fn assert_counted_file(name: &str) {
+  let path = data_path(name);
+  let lines = read_lines(&path);
+  let trajectories = read_trajectory_lines(&path).unwrap();
+
+  assert_eq!(lines[0].trim(), "2");
+  assert_eq!(trajectories.len(), lines[1].trim().parse::<usize>().unwrap());
+  let expected: Vec<usize> = lines[2..].iter()
+    .map(|line| line.split_whitespace().nth(1).unwrap().parse().unwrap())
+    .collect();
+  assert_eq!(point_counts(&trajectories), expected);
+  assert!(trajectories.iter().all(|trajectory| trajectory.get_point(0).unwrap().get_dimension() == 2));
+}
+
+#[test]
+fn load_elk() {
+  assert_counted_file("elk_1993.tra");
+}
+
+#[test]
+fn load_hurricane() {
+  assert_counted_file("hurricane1950_2006.tra");
+}
+
+#[test]
+fn load_deer() {
+  let path = data_path("deer_1995.tra");
+  let lines = read_lines(&path);
+  let trajectories = read_trajectory_lines(&path).unwrap();
+
+  // 只有维度行，每条记录为 `id x y ...`
+  assert_eq!(trajectories.len(), lines.len() - 1);
+  let expected: Vec<usize> = lines[1..].iter()
+    .map(|line| (line.split_whitespace().count() - 1) / 2)
+    .collect();
+  assert_eq!(point_counts(&trajectories), expected);
+  let ids: Vec<usize> = trajectories.iter().map(Trajectory::get_id).collect();
+  assert_eq!(ids, (0..trajectories.len()).collect::<Vec<usize>>());
+}
+
+#[test]
+fn trajectory_count_mismatch() {
+  let path = write_temp("trajectory-count.tra", "2\n3\n0 2 0 0 1 1\n1 1 5 5\n");
+  let e = read_trajectory_lines(&path).unwrap_err();
+  let (trajectories, warnings) = read_trajectory_lines_with_mode(&path, ErrorMode::SkipInvalid).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  match e.get_kind() {
+    FileErrorKind::TrajectoryCountMismatch { expected: 3, found: 2 } => {},
+    kind => panic!("unexpected error {:?}", kind)
+  }
+  assert_eq!(e.to_string(), format!("{}:2: expected 3 trajectories but found 2", path));
+  assert_eq!(trajectories.len(), 2);
+  assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn point_count_mismatch() {
+  let path = write_temp("point-count.tra", "2\n2\n0 3 0 0 1 1\n1 1 5 5\n");
+  let e = read_trajectory_lines(&path).unwrap_err();
+  let (trajectories, warnings) = read_trajectory_lines_with_mode(&path, ErrorMode::SkipInvalid).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  match e.get_kind() {
+    FileErrorKind::PointCountMismatch { expected: 3, found: 2 } => {},
+    kind => panic!("unexpected error {:?}", kind)
+  }
+  assert_eq!((e.get_line(), e.get_column(), e.get_token()), (Some(3), Some(2), Some("3")));
+  assert_eq!(e.to_string(), format!("{}:3:2: `3`: expected 3 points but found 2", path));
+  assert_eq!(trajectories.len(), 1);
+  assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn dimension_mismatch() {
+  let path = write_temp("dimension.tra", "2\n\n0 0 0 1\n1 0 0 1 1\n");
+  let e = read_trajectory_lines(&path).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  match e.get_kind() {
+    FileErrorKind::DimensionMismatch => {},
+    kind => panic!("unexpected error {:?}", kind)
+  }
+  // 空行也计入行号
+  assert_eq!(e.to_string(), format!("{}:3: the sum of coordinates isn't multiple of dimension", path));
+}
+
+#[test]
+fn invalid_coordinate_is_located() {
+  let path = write_temp("coordinate.tra", "2\n0 1 1 2 x\n");
+  let e = read_trajectory_lines(&path).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(e.to_string(), format!("{}:2:5: `x`: the coordinate of point isn't a f64", path));
+}
+
+#[test]
+fn unsupported_dimension() {
+  let path = write_temp("unsupported.tra", "4\n0 1 2 3 4\n");
+  let e = read_trajectory_lines(&path).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  match e.get_kind() {
+    FileErrorKind::UnsupportedDimension(4) => {},
+    kind => panic!("unexpected error {:?}", kind)
+  }
+}
+
+#[test]
+fn geojson_elevation_is_kept() {
+  let path = write_temp("elevation.geojson", r#"{
+    "type": "Feature",
+    "properties": { "id": 2 },
+    "geometry": {
+      "type": "LineString",
+      "coordinates": [[116.3, 39.9, 44.5], [116.4, 39.9, 50.0], [116.5, 40.0, 61.25, 7.0]]
+    }
+  }"#);
+  let trajectories = read_geojson_trajectories(&path, "id").unwrap();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(trajectories.len(), 1);
+  let points: Vec<Vec<f64>> = (0..3)
+    .map(|i| trajectories[0].get_point(i).unwrap().get_coords().to_vec())
+    .collect();
+  assert_eq!(points, vec![vec![116.3, 39.9, 44.5], vec![116.4, 39.9, 50.0], vec![116.5, 40.0, 61.25]]);
+}
+
+#[test]
+fn geojson_mixed_dimensions() {
+  let path = write_temp("mixed.geojson", r#"{
+    "type": "Feature",
+    "properties": { "id": 2 },
+    "geometry": { "type": "LineString", "coordinates": [[0, 0], [100, 0, 5]] }
+  }"#);
+  let e = read_geojson_trajectories(&path, "id").unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(e.to_string(), format!("{}: invalid GeoJSON: feature 0: positions of LineString have different dimensions", path));
+}
+
+/// 获得每条轨迹的 id 与所有点的坐标
+fn csv_points(trajectories: &[Trajectory]) -> Vec<(usize, Vec<Vec<f64>>)> {
+  trajectories.iter()
+    .map(|trajectory| {
+      let points = (0..trajectory.get_points_len())
+        .map(|i| trajectory.get_point(i).unwrap().get_coords().to_vec())
+        .collect();
+      (trajectory.get_id(), points)
+    })
+    .collect()
+}
+
+#[test]
+fn csv_quoted_fields() {
+  let path = write_temp("quoted.csv", concat!(
+    "traj_id,name,x,y\n",
+    "1,\"north, gate\",0,0\n",
+    "1,\"a \"\"quoted\"\" name\",\"10\",20\n",
+    "2,\"two\nlines\",5,5\n",
+    "\n",
+    "2,plain,6,\"7\"\n"
+  ));
+  let trajectories = read_csv_trajectories(&path, &CsvOptions::default()).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(csv_points(&trajectories), vec![
+    (1, vec![vec![0.0, 0.0], vec![10.0, 20.0]]),
+    (2, vec![vec![5.0, 5.0], vec![6.0, 7.0]])
+  ]);
+}
+
+#[test]
+fn csv_escaped_quote_in_token() {
+  let path = write_temp("escaped.csv", "traj_id,x,y\n1,\"1\"\"5\",2\n");
+  let e = read_csv_trajectories(&path, &CsvOptions::default()).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(e.to_string(), format!("{}:2:2: `1\"5`: the coordinate of point isn't a f64", path));
+}
+
+#[test]
+fn csv_line_number_after_multi_line_field() {
+  let path = write_temp("multi-line.csv", "traj_id,name,x,y\n1,\"a\nb\nc\",0,0\n1,d,x,0\n");
+  let e = read_csv_trajectories(&path, &CsvOptions::default()).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  // 第二条记录的引号字段占了第 2 到第 4 行
+  assert_eq!((e.get_line(), e.get_column(), e.get_token()), (Some(5), Some(3), Some("x")));
+}
+
+#[test]
+fn csv_unterminated_quote() {
+  let path = write_temp("unterminated.csv", "traj_id,x,y\n1,0,0\n1,1,1\n2,\"3,4\n2,5,6\n");
+  let e = read_csv_trajectories(&path, &CsvOptions::default()).unwrap_err();
+  let (trajectories, warnings) = read_csv_trajectories_with_mode(&path, &CsvOptions::default(),
+    ErrorMode::SkipInvalid).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  match e.get_kind() {
+    FileErrorKind::UnterminatedQuote => {},
+    kind => panic!("unexpected error {:?}", kind)
+  }
+  assert_eq!(e.to_string(), format!("{}:4: the quoted field isn't closed", path));
+  // 跳过时保留引号之前的记录
+  assert_eq!(csv_points(&trajectories), vec![(1, vec![vec![0.0, 0.0], vec![1.0, 1.0]])]);
+  assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn csv_delimiter_and_index_columns() {
+  let path = write_temp("tabs.csv", "7\t0\t1\t2\n7\t3\t4\t5\n8\t6\t7\t8\n");
+  let mut options = CsvOptions::new(CsvColumn::Index(0), CsvColumn::Index(2), CsvColumn::Index(1));
+  options.set_delimiter('\t');
+  options.set_has_header(false);
+  options.set_z_column(Some(CsvColumn::Index(3)));
+  let trajectories = read_csv_trajectories(&path, &options).unwrap();
+
+  // 按名字指定列时没有表头会报错
+  let e = read_csv_trajectories(&path, &CsvOptions::default()).unwrap_err();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(csv_points(&trajectories), vec![
+    (7, vec![vec![1.0, 0.0, 2.0], vec![4.0, 3.0, 5.0]]),
+    (8, vec![vec![7.0, 6.0, 8.0]])
+  ]);
+  match e.get_kind() {
+    FileErrorKind::MissingColumn(column) => assert_eq!(column, "traj_id"),
+    kind => panic!("unexpected error {:?}", kind)
+  }
+}
+
+#[test]
+fn csv_sorted_by_time() {
+  let path = write_temp("time.csv", "traj_id;t;x;y\n3;30;3;0\n4;5;0;9\n3;10;1;0\n3;20;2;0\n");
+  let mut options = CsvOptions::default();
+  options.set_delimiter(';');
+  options.set_time_column(Some(CsvColumn::parse("t")));
+  let trajectories = read_csv_trajectories(&path, &options).unwrap();
+
+  // 没有时间列时保持文件中的顺序
+  options.set_time_column(None);
+  let unsorted = read_csv_trajectories(&path, &options).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  // 轨迹按照 id 第一次出现的顺序排列，同一轨迹的点按时间排序
+  assert_eq!(csv_points(&trajectories), vec![
+    (3, vec![vec![1.0, 0.0], vec![2.0, 0.0], vec![3.0, 0.0]]),
+    (4, vec![vec![0.0, 9.0]])
+  ]);
+  let times: Vec<Option<f64>> = (0..3).map(|i| trajectories[0].get_point(i).unwrap().get_t()).collect();
+  assert_eq!(times, vec![Some(10.0), Some(20.0), Some(30.0)]);
+  assert_eq!(csv_points(&unsorted)[0].1, vec![vec![3.0, 0.0], vec![1.0, 0.0], vec![2.0, 0.0]]);
+  assert_eq!(unsorted[0].get_point(0).unwrap().get_t(), None);
+}
+
+/// 四条带时间戳的水平线段：线段 2 是噪声，线段 3 所在的簇 1 没有生成代表轨迹而被舍弃
+fn labelled_segments() -> (Vec<LineSegment>, Vec<i32>, Vec<Cluster>) {
+  let line_segment = |trajectory_id: usize, part: usize, ordinal: usize, point_range: (usize, usize), y: f64| {
+    LineSegment::new(SegmentId::new(trajectory_id, part, ordinal), point_range,
+      Point::with_time(0.0, y, 1.0), Point::with_time(100.0, y, 2.0))
+  };
+  let line_segments = vec![
+    line_segment(4, 0, 0, (0, 2), 0.0),
+    line_segment(5, 1, 3, (3, 4), 5.0),
+    line_segment(6, 0, 1, (1, 3), 500.0),
+    line_segment(7, 0, 0, (0, 1), 900.0)
+  ];
+  let mut cluster = Cluster::new(0, 0, 2, vec![4, 5], vec![Point::new(0.0, 2.5), Point::new(100.0, 2.5)]);
+  cluster.set_time_range(Some((1.0, 2.0)));
+
+  (line_segments, vec![0, 0, -1, 1], vec![cluster])
+}
+
+#[test]
+fn feature_collection_properties() {
+  let (line_segments, cluster_indexs, clusters) = labelled_segments();
+
+  let collection = to_feature_collection(&clusters, &line_segments, &cluster_indexs);
+  let features = collection["features"].as_array().unwrap();
+  assert_eq!(collection["type"], "FeatureCollection");
+  assert_eq!(features.len(), 5);
+
+  assert_eq!(features[0]["geometry"], json!({ "type": "LineString", "coordinates": [[0.0, 2.5], [100.0, 2.5]] }));
+  assert_eq!(features[0]["properties"]["kind"], "cluster");
+  assert_eq!(features[0]["properties"]["cluster_id"], 0);
+  assert_eq!(features[0]["properties"]["trajectory_ids"], json!([4, 5]));
+
+  let segment = |index: usize, key: &str| -> Value { features[index + 1]["properties"][key].clone() };
+  let keys = ["trajectory_id", "trajectory_part", "segment_ordinal", "point_range", "segment_cluster", "cluster_id", "noise"];
+  let expected = [
+    json!([4, 0, 0, [0, 2], 0, 0, false]),
+    json!([5, 1, 3, [3, 4], 0, 0, false]),
+    json!([6, 0, 1, [1, 3], null, null, true]),
+    json!([7, 0, 0, [0, 1], 1, null, false])
+  ];
+  for (index, expected) in expected.iter().enumerate() {
+    let actual: Vec<Value> = keys.iter().map(|key| segment(index, key)).collect();
+    assert_eq!(&Value::from(actual), expected, "segment {}", index);
+    assert_eq!(segment(index, "kind"), "segment");
+    assert_eq!(segment(index, "segment_index"), index);
+    assert_eq!((segment(index, "start_time"), segment(index, "end_time")), (json!(1.0), json!(2.0)));
+  }
+  assert_eq!(features[3]["geometry"], json!({ "type": "LineString", "coordinates": [[0.0, 500.0], [100.0, 500.0]] }));
+}
+
+#[test]
+fn labels_json() {
+  let (line_segments, cluster_indexs, clusters) = labelled_segments();
+  let dir = temp_dir("labels-json");
+  let out_path = dir.join("labels.json");
+
+  write_labels_json(out_path.to_str().unwrap(), &clusters, &line_segments, &cluster_indexs).unwrap();
+  let labels: Value = serde_json::from_str(&fs::read_to_string(&out_path).unwrap()).unwrap();
+
+  let keys = ["trajectory_id", "trajectory_part", "segment_ordinal", "point_range", "segment_cluster", "cluster_id", "noise"];
+  let expected = [
+    json!([4, 0, 0, [0, 2], 0, 0, false]),
+    json!([5, 1, 3, [3, 4], 0, 0, false]),
+    json!([6, 0, 1, [1, 3], -1, null, true]),
+    json!([7, 0, 0, [0, 1], 1, null, false])
+  ];
+  let segments = labels["segments"].as_array().unwrap();
+  assert_eq!(segments.len(), expected.len());
+  for (index, (segment, expected)) in segments.iter().zip(expected.iter()).enumerate() {
+    let actual: Vec<Value> = keys.iter().map(|key| segment[key].clone()).collect();
+    assert_eq!(&Value::from(actual), expected, "segment {}", index);
+    assert_eq!(segment["segment_index"], index);
+  }
+  assert_eq!(segments[2]["start"], json!([0.0, 500.0]));
+  assert_eq!(segments[2]["end"], json!([100.0, 500.0]));
+
+  assert_eq!(labels["clusters"], json!([{
+    "cluster_id": 0,
+    "segment_cluster": 0,
+    "num_segments": 2,
+    "trajectory_ids": [4, 5],
+    "start_time": 1.0,
+    "end_time": 2.0,
+    "points": [[0.0, 2.5], [100.0, 2.5]]
+  }]));
+
+  let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn labels_csv() {
+  let (line_segments, cluster_indexs, clusters) = labelled_segments();
+  let mut out = Vec::new();
+
+  write_labels_csv_to(&mut out, &clusters, &line_segments, &cluster_indexs).unwrap();
+
+  assert_eq!(String::from_utf8(out).unwrap(), "\
+segment_index,trajectory_id,trajectory_part,segment_ordinal,start_point,end_point,start_x,start_y,end_x,end_y,start_time,end_time,segment_cluster,cluster_id,noise
+0,4,0,0,0,2,0,0,100,0,1,2,0,0,false
+1,5,1,3,3,4,0,5,100,5,1,2,0,0,false
+2,6,0,1,1,3,0,500,100,500,1,2,-1,,true
+3,7,0,0,0,1,0,900,100,900,1,2,1,,false
+");
+
+  // 三维线段输出 z 列，没有时间戳的线段时间为空
+  let line_segments = vec![LineSegment::new(SegmentId::new(0, 0, 0), (0, 1),
+    Point::new_3d(0.0, 0.0, 1.0), Point::new_3d(10.0, 0.0, 2.0))];
+  let mut out = Vec::new();
+  write_labels_csv_to(&mut out, &[], &line_segments, &[-1]).unwrap();
+  let out = String::from_utf8(out).unwrap();
+  let lines: Vec<&str> = out.lines().collect();
+  assert!(lines[0].contains("start_x,start_y,start_z,end_x,end_y,end_z"));
+  assert_eq!(lines[1], "0,0,0,0,0,1,0,0,1,10,0,2,,,-1,,true");
+}
+
+#[test]
+fn cluster_members_csv() {
+  let (_, _, mut clusters) = labelled_segments();
+  clusters.push(Cluster::new(1, 3, 4, vec![2, 8, 9], vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0)]));
+  let mut out = Vec::new();
+
+  write_cluster_members_csv_to(&mut out, &clusters).unwrap();
+
+  // 每个簇的每条成员轨迹一行，没有时间范围的簇时间为空
+  assert_eq!(String::from_utf8(out).unwrap(), "\
+cluster_id,segment_cluster,num_segments,start_time,end_time,trajectory_id
+0,0,2,1,2,4
+0,0,2,1,2,5
+1,3,4,,,2
+1,3,4,,,8
+1,3,4,,,9
+");
+}
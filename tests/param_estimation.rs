@@ -0,0 +1,76 @@
+//! 参数估计的测试
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    line_segment::{LineSegment, SegmentId}
+  },
+  distance_config::DistanceConfig,
+  param_estimation::{estimate_parameters, MAX_CANDIDATE_EPS}
+};
+
+/// 长度为 100 的水平线段，前两条相距 5，第三条离得很远
+fn three_segments() -> Vec<LineSegment> {
+  [0.0, 5.0, 1000.0].iter()
+    .enumerate()
+    .map(|(id, y)| LineSegment::new(SegmentId::new(id, 0, 0), (0, 1), Point::new(0.0, *y), Point::new(100.0, *y)))
+    .collect()
+}
+
+#[test]
+fn entropy_of_a_tiny_input() {
+  let estimate = estimate_parameters(&three_segments(), &DistanceConfig::default(), 0.0, 10.0, 5.0).unwrap();
+
+  // eps 为 0 时邻域大小为 [1, 1, 1]，H = log2(3)；
+  // eps 为 5 与 10 时邻域大小为 [2, 2, 1]，H = -2 · 0.4 · log2(0.4) - 0.2 · log2(0.2)
+  let skewed = -2.0 * 0.4 * 0.4f64.log2() - 0.2 * 0.2f64.log2();
+  let expected = [(0.0, 3.0f64.log2()), (5.0, skewed), (10.0, skewed)];
+  assert_eq!(estimate.get_entropies().len(), expected.len());
+  for ((eps, entropy), (expected_eps, expected_entropy)) in estimate.get_entropies().iter().zip(expected.iter()) {
+    assert_eq!(eps, expected_eps);
+    assert!((entropy - expected_entropy).abs() < 1e-12, "eps {}: {} != {}", eps, entropy, expected_entropy);
+  }
+
+  // 熵相同时取较小的 eps，平均邻域大小为 5/3，MinLns 为 round(5/3 + 2)
+  assert_eq!(estimate.get_eps(), 5.0);
+  assert!((estimate.get_avg_neighbourhood_size() - 5.0 / 3.0).abs() < 1e-12);
+  assert_eq!(estimate.get_min_lns(), 4);
+}
+
+#[test]
+fn max_eps_is_searched() {
+  let estimate = estimate_parameters(&three_segments(), &DistanceConfig::default(), 1.0, 1.3, 0.1).unwrap();
+  let candidates: Vec<f64> = estimate.get_entropies().iter().map(|(eps, _)| *eps).collect();
+
+  assert_eq!(candidates.len(), 4);
+  assert_eq!(*candidates.last().unwrap(), 1.3);
+}
+
+#[test]
+fn invalid_ranges() {
+  let line_segments = three_segments();
+  let config = DistanceConfig::default();
+  let ranges = [
+    (10.0, 5.0, 1.0),
+    (0.0, 10.0, 0.0),
+    (0.0, 10.0, -1.0),
+    (-5.0, 10.0, 1.0),
+    (f64::NAN, 10.0, 1.0),
+    (0.0, f64::INFINITY, 1.0),
+    (0.0, 10.0, f64::NAN),
+    (0.0, 1.0, 1.0 / MAX_CANDIDATE_EPS as f64 / 2.0)
+  ];
+
+  for (min_eps, max_eps, step) in ranges.iter() {
+    assert!(estimate_parameters(&line_segments, &config, *min_eps, *max_eps, *step).is_none(),
+      "range {:?} should be rejected", (min_eps, max_eps, step));
+  }
+  assert!(estimate_parameters(&[], &config, 0.0, 10.0, 1.0).is_none());
+
+  // 恰好 MAX_CANDIDATE_EPS 个候选 eps 仍然可以搜索
+  let step = 1.0;
+  let max_eps = (MAX_CANDIDATE_EPS - 1) as f64 * step;
+  let estimate = estimate_parameters(&line_segments, &config, 0.0, max_eps, step).unwrap();
+  assert_eq!(estimate.get_entropies().len(), MAX_CANDIDATE_EPS);
+}
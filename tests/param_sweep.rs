@@ -0,0 +1,83 @@
+//! 参数扫描的测试
+extern crate traclus;
+
+use rayon::ThreadPoolBuilder;
+use traclus::{
+  models::{
+    point::Point,
+    line_segment::{LineSegment, SegmentId}
+  },
+  dbscan::perform_dbscan,
+  distance_config::DistanceConfig,
+  evaluation::evaluate_clustering,
+  param_sweep::{
+    sweep_parameters,
+    sweep_parameters_with_gamma,
+    SweepResult
+  }
+};
+
+/// 两束相距很远的平行线段，每束四条，来自不同的轨迹
+fn two_bundles() -> Vec<LineSegment> {
+  (0..8)
+    .map(|id| {
+      let y = if id < 4 { id as f64 * 5.0 } else { 1000.0 + (id - 4) as f64 * 5.0 };
+      LineSegment::new(SegmentId::new(id, 0, 0), (0, 1), Point::new(0.0, y), Point::new(100.0, y))
+    })
+    .collect()
+}
+
+fn summary(results: &[SweepResult]) -> Vec<(f64, usize, usize, f64)> {
+  results.iter()
+    .map(|result| (result.get_eps(), result.get_min_lns(), result.get_num_of_clusters(), result.get_noise_ratio()))
+    .collect()
+}
+
+#[test]
+fn best_pair_separates_the_bundles() {
+  let line_segments = two_bundles();
+  let config = DistanceConfig::default();
+
+  let results = sweep_parameters(&line_segments, &config, &[1.0, 20.0, 2000.0], &[4, 5], None);
+
+  // eps 为 1 时所有线段都是噪声，eps 为 2000 时两束合为一个簇，MinLns 为 5 时每束的线段都不够
+  assert_eq!(summary(&results), vec![
+    (1.0, 4, 0, 1.0), (1.0, 5, 0, 1.0),
+    (20.0, 4, 2, 0.0), (20.0, 5, 0, 1.0),
+    (2000.0, 4, 1, 0.0), (2000.0, 5, 1, 0.0)
+  ]);
+
+  let best = results.iter()
+    .min_by(|lhs, rhs| lhs.get_q_measure().partial_cmp(&rhs.get_q_measure()).unwrap())
+    .unwrap();
+  assert_eq!((best.get_eps(), best.get_min_lns()), (20.0, 4));
+
+  // 复用的距离得到的 QMeasure 与单独评估的相同
+  for result in &results {
+    let (cluster_indexs, num_of_clusters) = perform_dbscan(result.get_eps(), result.get_min_lns(),
+      result.get_min_lns(), &line_segments, &config);
+    let expected = evaluate_clustering(&line_segments, &cluster_indexs, num_of_clusters, &config).get_q_measure();
+    assert!((result.get_q_measure() - expected).abs() <= expected * 1e-12, "{:?}", result);
+  }
+}
+
+#[test]
+fn sweep_in_pool_with_gamma() {
+  let line_segments = two_bundles();
+  let config = DistanceConfig::default();
+  let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+
+  let results = pool.install(|| {
+    sweep_parameters_with_gamma(&line_segments, &config, &[20.0], &[4], Some(2), 10.0)
+  });
+  let expected = sweep_parameters(&line_segments, &config, &[20.0], &[4], Some(2));
+
+  assert_eq!(summary(&results), summary(&expected));
+  assert_eq!(results[0].get_q_measure(), expected[0].get_q_measure());
+}
+
+#[test]
+fn empty_sweep() {
+  assert!(sweep_parameters(&[], &DistanceConfig::default(), &[20.0], &[4], None).is_empty());
+  assert!(sweep_parameters(&two_bundles(), &DistanceConfig::default(), &[], &[4], None).is_empty());
+}
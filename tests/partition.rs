@@ -0,0 +1,95 @@
+//! 轨迹划分配置的测试
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    trajectory::Trajectory
+  },
+  distance_config::DistanceConfig,
+  partition_tra::{
+    partition_trajectories,
+    get_partition_line
+  },
+  partition_config::{
+    PartitionConfig,
+    DEFAULT_MIN_LINE_SEGMENT_LENGTH
+  },
+  cluster_gen::DEFAULT_GAMMA
+};
+
+/// 沿 x 轴每隔 40 摆动一次的锯齿轨迹，共 9 个点
+fn zigzag(amplitude: f64) -> Trajectory {
+  let mut trajectory = Trajectory::new(0);
+  for i in 0..9 {
+    trajectory.add_point(Point::new(i as f64 * 40.0, if i % 2 == 1 { amplitude } else { 0.0 }));
+  }
+  trajectory
+}
+
+/// 划分锯齿轨迹，返回划分点与线段端点在原轨迹中的序号
+fn partition(amplitude: f64, partition_config: &PartitionConfig) -> (Vec<usize>, Vec<(usize, usize)>) {
+  let config = DistanceConfig::default();
+  let thick_trajectories = partition_trajectories(vec![zigzag(amplitude)], &config, partition_config);
+  let partition_points = (0..thick_trajectories[0].get_len())
+    .map(|i| thick_trajectories[0].get_point_index(i).unwrap())
+    .collect();
+  let point_ranges = get_partition_line(&thick_trajectories, &config, partition_config).iter()
+    .map(|line_segment| line_segment.get_point_range())
+    .collect();
+
+  (partition_points, point_ranges)
+}
+
+#[test]
+fn min_segment_length_merges_short_segments() {
+  let every_point: Vec<(usize, usize)> = (0..8).map(|i| (i, i + 1)).collect();
+
+  // 没有代价优势时每个拐点都划分，约 40 长的线段都不短于 10
+  let (partition_points, point_ranges) = partition(10.0, &PartitionConfig::new(0, 10.0));
+  assert_eq!(partition_points, (0..9).collect::<Vec<usize>>());
+  assert_eq!(point_ranges, every_point);
+
+  // 最短长度为 50 时离上一个划分点只有约 40 的拐点不再划分，相邻的两条短线段合并为一条
+  let (partition_points, point_ranges) = partition(10.0, &PartitionConfig::new(0, 50.0));
+  assert_eq!(partition_points, vec![0, 2, 4, 6, 8]);
+  assert_eq!(point_ranges, vec![(0, 2), (2, 4), (4, 6), (6, 8)]);
+
+  // 整条轨迹都短于最短长度时没有线段
+  let (partition_points, point_ranges) = partition(10.0, &PartitionConfig::new(0, 400.0));
+  assert_eq!(partition_points, vec![0, 8]);
+  assert!(point_ranges.is_empty());
+}
+
+#[test]
+fn mdl_advantage_reduces_partitions() {
+  let every_point: Vec<usize> = (0..9).collect();
+
+  assert_eq!(partition(20.0, &PartitionConfig::new(5, 10.0)).0, every_point);
+  assert_eq!(partition(20.0, &PartitionConfig::new(25, 10.0)).0, vec![0, 5, 8]);
+  assert_eq!(partition(20.0, &PartitionConfig::new(100, 10.0)).0, vec![0, 8]);
+}
+
+#[test]
+fn precisions_change_partitioning() {
+  let every_point: Vec<usize> = (0..9).collect();
+  let with_precision = |set: &dyn Fn(&mut PartitionConfig)| {
+    let mut partition_config = PartitionConfig::new(0, 10.0);
+    set(&mut partition_config);
+    partition(10.0, &partition_config).0
+  };
+
+  assert_eq!(with_precision(&|_| ()), every_point);
+  // 垂直距离与角度距离都小于精度时偏离的代价与不偏离相同，不再划分
+  assert_eq!(with_precision(&|config| config.set_precision(10.0)), vec![0, 8]);
+  // 只放宽一种距离的精度时另一种距离的代价仍然足以划分
+  assert_eq!(with_precision(&|config| config.set_perpendicular_precision(10.0)), every_point);
+  assert_eq!(with_precision(&|config| config.set_angle_precision(10.0)), every_point);
+  // 精度为 100 时短于 100 的线段没有代价，跨过三段、长为 120 的划分线段代价为 1，每两段划分一次
+  assert_eq!(with_precision(&|config| config.set_precision(100.0)), vec![0, 2, 4, 6, 8]);
+}
+
+#[test]
+fn default_gamma_follows_min_segment_length() {
+  assert_eq!(DEFAULT_GAMMA, DEFAULT_MIN_LINE_SEGMENT_LENGTH / 1.414);
+}
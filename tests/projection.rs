@@ -0,0 +1,79 @@
+//! 坐标投影的测试
+extern crate traclus;
+
+use traclus::{
+  models::{
+    point::Point,
+    trajectory::Trajectory
+  },
+  projection::{Projection, ProjectionKind}
+};
+
+/// 获得经纬度点所在的 UTM 投影
+fn utm_of(lon: f64, lat: f64) -> Projection {
+  let mut trajectory = Trajectory::new(0);
+  trajectory.add_point(Point::new(lon, lat));
+
+  Projection::from_trajectories(ProjectionKind::Utm, &[trajectory]).unwrap()
+}
+
+#[test]
+fn utm_zone_and_hemisphere() {
+  match utm_of(2.2945, 48.8583) {
+    Projection::Utm { zone: 31, north: true } => {},
+    projection => panic!("unexpected projection {:?}", projection)
+  }
+  match utm_of(151.2153, -33.8568) {
+    Projection::Utm { zone: 56, north: false } => {},
+    projection => panic!("unexpected projection {:?}", projection)
+  }
+  match utm_of(-179.9, 10.0) {
+    Projection::Utm { zone: 1, north: true } => {},
+    projection => panic!("unexpected projection {:?}", projection)
+  }
+}
+
+#[test]
+fn utm_reference_coordinates() {
+  // 参考值由 6 阶 Krüger 级数计算，Snyder 公式在分带内的误差在毫米级
+  let references = [
+    (Projection::utm(31, true), (2.2945, 48.8583), (448_251.898_3, 5_411_943.793_8)),
+    (Projection::utm(56, false), (151.2153, -33.8568), (334_900.569_7, 6_252_288.752_9)),
+    (Projection::utm(18, true), (-75.0, 40.0), (500_000.0, 4_427_757.218_7))
+  ];
+
+  for (projection, (lon, lat), (easting, northing)) in references.iter() {
+    let projected = projection.project(&Point::new(*lon, *lat));
+
+    assert!((projected.get_x() - easting).abs() < 0.01, "{:?} -> {:?}", (lon, lat), projected);
+    assert!((projected.get_y() - northing).abs() < 0.01, "{:?} -> {:?}", (lon, lat), projected);
+  }
+}
+
+#[test]
+fn utm_round_trip() {
+  for lat in [-80.0, -60.5, -33.3, -5.0, 0.0, 5.0, 33.3, 60.5, 84.0].iter() {
+    for offset in [-3.0, -1.2, 0.0, 0.7, 3.0].iter() {
+      let lon = 117.0 + offset;
+      let projection = utm_of(117.0, *lat);
+      let point = Point::with_time(lon, *lat, 42.0);
+      let restored = projection.unproject(&projection.project(&point));
+
+      assert!((restored.get_x() - lon).abs() < 1e-7, "{:?} -> {:?}", point, restored);
+      assert!((restored.get_y() - lat).abs() < 1e-7, "{:?} -> {:?}", point, restored);
+      assert_eq!(restored.get_t(), point.get_t());
+    }
+  }
+}
+
+#[test]
+fn local_enu_round_trip() {
+  let projection = Projection::local_enu(-122.4, 37.8);
+  for (lon, lat) in [(-122.4, 37.8), (-122.5, 37.7), (-122.3, 37.95)].iter() {
+    let point = Point::new(*lon, *lat);
+    let restored = projection.unproject(&projection.project(&point));
+
+    assert!((restored.get_x() - lon).abs() < 1e-9);
+    assert!((restored.get_y() - lat).abs() < 1e-9);
+  }
+}
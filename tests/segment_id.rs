@@ -0,0 +1,83 @@
+//! 线段标识的唯一性测试，输入中有重复的轨迹 id 时线段的标识也不能相同
+extern crate traclus;
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use traclus::{
+  models::{
+    line_segment::{LineSegment, SegmentId},
+    trajectory::Trajectory
+  },
+  file_io::{
+    read_trajectory_lines,
+    geojson::read_geojson_trajectories
+  },
+  partition_tra::{
+    partition_trajectories,
+    get_partition_line
+  },
+  distance_config::DistanceConfig,
+  partition_config::PartitionConfig
+};
+
+/// 将内容写入临时文件，返回文件路径
+fn write_temp(name: &str, content: &str) -> PathBuf {
+  let path = env::temp_dir().join(format!("traclus-segment-id-{}-{}", std::process::id(), name));
+  fs::write(&path, content).unwrap();
+  path
+}
+
+fn partition(trajectories: Vec<Trajectory>) -> Vec<LineSegment> {
+  let distance_config = DistanceConfig::default();
+  let partition_config = PartitionConfig::default();
+  let thick_trajectories = partition_trajectories(trajectories, &distance_config, &partition_config);
+  get_partition_line(&thick_trajectories, &distance_config, &partition_config)
+}
+
+fn assert_unique(line_segments: &[LineSegment]) {
+  let ids: HashSet<SegmentId> = line_segments.iter().map(LineSegment::get_id).collect();
+  assert_eq!(ids.len(), line_segments.len());
+
+  let distinct: HashSet<&LineSegment> = line_segments.iter().collect();
+  assert_eq!(distinct.len(), line_segments.len());
+}
+
+#[test]
+fn multi_line_string_parts_get_distinct_ids() {
+  let path = write_temp("multi.geojson", r#"{
+    "type": "Feature",
+    "properties": { "id": 1 },
+    "geometry": {
+      "type": "MultiLineString",
+      "coordinates": [[[0, 0], [100, 0]], [[0, 50], [100, 50]]]
+    }
+  }"#);
+  let trajectories = read_geojson_trajectories(path.to_str().unwrap(), "id").unwrap();
+  fs::remove_file(&path).unwrap();
+
+  assert_eq!(trajectories.iter().map(Trajectory::get_part).collect::<Vec<usize>>(), vec![0, 1]);
+
+  let line_segments = partition(trajectories);
+  assert_eq!(line_segments.len(), 2);
+  assert_eq!(line_segments[0].get_id(), SegmentId::new(1, 0, 0));
+  assert_eq!(line_segments[1].get_id(), SegmentId::new(1, 1, 0));
+  assert_unique(&line_segments);
+}
+
+#[test]
+fn repeated_tra_ids_get_distinct_ids() {
+  let path = write_temp("repeated.tra", "2\n3 0 0 100 0\n4 0 20 100 20\n3 0 50 100 50\n");
+  let trajectories = read_trajectory_lines(path.to_str().unwrap()).unwrap();
+  fs::remove_file(&path).unwrap();
+
+  let parts: Vec<(usize, usize)> = trajectories.iter()
+    .map(|trajectory| (trajectory.get_id(), trajectory.get_part()))
+    .collect();
+  assert_eq!(parts, vec![(3, 0), (4, 0), (3, 1)]);
+
+  let line_segments = partition(trajectories);
+  assert_eq!(line_segments.len(), 3);
+  assert_unique(&line_segments);
+}
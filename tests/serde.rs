@@ -0,0 +1,144 @@
+//! 模型与聚类结果的 JSON 序列化往返测试，需要启用 `serde` 特性
+#![cfg(feature = "serde")]
+
+extern crate traclus;
+
+use serde::{de::DeserializeOwned, Serialize};
+use traclus::{
+  models::{
+    point::Point,
+    trajectory::Trajectory,
+    line_segment::{LineSegment, SegmentId},
+    line_segment_cluster::LineSegmentCluster,
+    candidate_point::CandidatePoint
+  },
+  clustering::{
+    TraClus,
+    ClusteringResult
+  }
+};
+
+/// 序列化为 JSON 后再反序列化
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+  let json = serde_json::to_string(value).unwrap();
+  serde_json::from_str(&json).unwrap()
+}
+
+/// 几条相互平行、带时间戳的轨迹，聚类后得到一个簇
+fn parallel_trajectories() -> Vec<Trajectory> {
+  (0..6)
+    .map(|id| {
+      let mut trajectory = Trajectory::new(id);
+      for i in 0..10 {
+        trajectory.add_point(Point::with_time(i as f64 * 100.0, id as f64 * 5.0 + 0.1, i as f64 * 60.0));
+      }
+      trajectory
+    })
+    .collect()
+}
+
+fn run_traclus() -> ClusteringResult {
+  TraClus::new(30.0, 3).run(parallel_trajectories()).unwrap()
+}
+
+#[test]
+fn point_round_trip() {
+  for point in &[Point::new(1.5, -2.25), Point::new_3d(0.1, 0.2, 0.3), Point::with_time(3.0, 4.0, 1e9 + 0.5)] {
+    let restored = round_trip(point);
+
+    assert_eq!(restored.get_dimension(), point.get_dimension());
+    assert_eq!(restored.get_coords(), point.get_coords());
+    assert_eq!(restored.get_t(), point.get_t());
+  }
+}
+
+#[test]
+fn invalid_point_dimension_is_rejected() {
+  let payloads = [
+    r#"{"coords":[1.0,2.0,3.0],"dimension":4,"t":null}"#,
+    r#"{"coords":[1.0,2.0,0.0],"dimension":1,"t":null}"#,
+    r#"{"coords":[],"dimension":0,"t":null}"#,
+    r#"{"coords":[1.0,2.0,3.0],"dimension":2,"t":null}"#
+  ];
+  for payload in payloads.iter() {
+    assert!(serde_json::from_str::<Point>(payload).is_err(), "{} should be rejected", payload);
+  }
+
+  // 嵌套在其他类型中的点同样会被检查
+  let line_segment = r#"{"id":{"trajectory_id":0,"part":0,"segment_index":0},"point_range":[0,1],
+    "start_point":{"coords":[0.0,0.0,0.0],"dimension":2,"t":null},
+    "end_point":{"coords":[1.0,0.0,0.0],"dimension":4,"t":null}}"#;
+  assert!(serde_json::from_str::<LineSegment>(line_segment).is_err());
+  assert!(serde_json::from_str::<LineSegment>(&line_segment.replace("\"dimension\":4", "\"dimension\":2")).is_ok());
+
+  let point: Point = serde_json::from_str(r#"{"coords":[1.0,2.0,3.0],"dimension":3,"t":5.0}"#).unwrap();
+  assert_eq!(point.get_coords(), &[1.0, 2.0, 3.0]);
+  assert_eq!(point.get_t(), Some(5.0));
+}
+
+#[test]
+fn trajectory_round_trip() {
+  for trajectory in parallel_trajectories() {
+    assert_eq!(format!("{:?}", round_trip(&trajectory)), format!("{:?}", trajectory));
+  }
+}
+
+#[test]
+fn line_segment_round_trip() {
+  let line_segment = LineSegment::new(SegmentId::new(7, 1, 2), (4, 9),
+    Point::with_time(0.5, 1.0, 10.0), Point::with_time(80.0, 1.0, 20.0));
+  let restored = round_trip(&line_segment);
+
+  assert_eq!(restored, line_segment);
+  assert_eq!(restored.get_id(), line_segment.get_id());
+  assert_eq!(restored.get_point_range(), (4, 9));
+  assert_eq!(format!("{:?}", restored), format!("{:?}", line_segment));
+}
+
+#[test]
+fn line_segment_cluster_round_trip() {
+  let mut cluster = LineSegmentCluster::new(3);
+  cluster.insert(1);
+  cluster.insert(5);
+  cluster.insert(2);
+  cluster.push(CandidatePoint::new(0, 12.5));
+  cluster.add_point(Point::new(1.0, 2.0));
+  cluster.extend_time_range(4.0);
+  cluster.enable();
+  let restored = round_trip(&cluster);
+
+  assert_eq!(restored.get_id(), cluster.get_id());
+  assert_eq!(restored.get_trajectory_ids(), cluster.get_trajectory_ids());
+  assert_eq!(restored.get_time_range(), cluster.get_time_range());
+  assert_eq!(restored.get_enable(), cluster.get_enable());
+  assert_eq!(restored.len(), cluster.len());
+  assert_eq!(format!("{:?}", restored.get_points()), format!("{:?}", cluster.get_points()));
+}
+
+#[test]
+fn clustering_result_round_trip() {
+  let result = run_traclus();
+  assert!(!result.get_clusters().is_empty());
+
+  let restored = round_trip(&result);
+
+  assert_eq!(restored.get_statistics(), result.get_statistics());
+  assert_eq!(restored.get_cluster_indexs(), result.get_cluster_indexs());
+  assert_eq!(restored.get_line_segments(), result.get_line_segments());
+  assert_eq!(format!("{:?}", restored), format!("{:?}", result));
+}
+
+#[test]
+fn restored_line_segments_recluster_identically() {
+  let result = run_traclus();
+  let traclus = TraClus::new(30.0, 3);
+  let line_segments: Vec<LineSegment> = round_trip(result.get_line_segments());
+  let reclustered = traclus.cluster_line_segments(line_segments).unwrap();
+
+  assert_eq!(reclustered.get_cluster_indexs(), result.get_cluster_indexs());
+  assert_eq!(reclustered.get_clusters().len(), result.get_clusters().len());
+  for (lhs, rhs) in reclustered.get_clusters().iter().zip(result.get_clusters()) {
+    assert_eq!(lhs.get_trajectory_ids(), rhs.get_trajectory_ids());
+    assert_eq!(lhs.get_num_of_line_segments(), rhs.get_num_of_line_segments());
+  }
+}